@@ -4,14 +4,18 @@ use axum::response::IntoResponse;
 use serde::{Deserialize, Serialize};
 
 use rustychess::core::movegen::MoveGenerator;
-use rustychess::core::{Board, Move as EngineMove, PieceType};
+use rustychess::core::{Board, Move as EngineMove, MoveList, PieceType};
 
 use rustychess::evaluate::evaluate;
-use rustychess::search::Search;
+use rustychess::search::{Search, SearchLimits};
 // ===== Your protocol types (as discussed) =====
 use axum::{routing::get, Router};
 
 use std::sync::{Arc, Mutex};
+
+/// How long the engine thinks about each of its moves.
+const ENGINE_MOVETIME_MS: u64 = 1000;
+
 #[tokio::main]
 async fn main() {
     let app = Router::new()
@@ -61,6 +65,9 @@ pub struct State {
     pub eval: Option<i32>,         // NEW: engine evaluation (centipawns or mate score)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub best_move: Option<Move>,
+    /// The engine's expected line, starting with the move it just played.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pv: Vec<Move>,
 }
 
 
@@ -75,20 +82,17 @@ async fn handle_socket(mut socket: WebSocket) {
     let movegen = MoveGenerator::new();
     let mut board = Board::new();
     let searcher = Arc::new(Mutex::new(Search::new(true)));
-    //init neural network
-    {
-        board.set_startpos(&searcher.lock().unwrap().nnue);
-    }
+    board.set_startpos();
 
     // Engine result channel: search task -> socket loop
-    let (engine_tx, mut engine_rx) = mpsc::unbounded_channel::<(EngineMove, i32)>();
+    let (engine_tx, mut engine_rx) = mpsc::unbounded_channel::<(EngineMove, i32, Vec<EngineMove>)>();
 
     // (depth, move, score, thinking_flag_for_state)
     let mut playerside: u8 = 0;
     let mut thinking = false;
 
     // Initial state
-    if send_json(&mut socket, &ServerMsg::State(make_state(&mut board, &movegen, thinking, Some(0), None)))
+    if send_json(&mut socket, &ServerMsg::State(make_state(&mut board, &movegen, thinking, Some(0), None, &[])))
         .await
         .is_err()
     {
@@ -99,7 +103,7 @@ async fn handle_socket(mut socket: WebSocket) {
         tokio::select! {
             // 1) Engine finished thinking
             maybe_best = engine_rx.recv() => {
-                let Some((best_move, best_score)) = maybe_best else { return; };
+                let Some((best_move, best_score, pv)) = maybe_best else { return; };
                 
 
                 // // 1) Send a hint/update BEFORE mutating the board
@@ -117,12 +121,13 @@ async fn handle_socket(mut socket: WebSocket) {
 
                 eprintln!("SERVER SEND eval_cp={}", -best_score);
                 let final_state = {
-                    let mut s = searcher.lock().unwrap();
-                    board.push(best_move, &movegen, &s.nnue);
-                    let nn = s.nnue.eval_cp_like(&board);
-                    eprintln!("NNUE eval after push: {}", nn);
+                    let s = searcher.lock().unwrap();
+                    board.push(best_move, &movegen);
+                    if let Some(nnue) = s.net() {
+                        eprintln!("NNUE eval after push: {}", nnue.eval_cp_like(&board));
+                    }
                     thinking = false;
-                    make_state(&mut board, &movegen, thinking, Some(best_score), None)
+                    make_state(&mut board, &movegen, thinking, Some(best_score), None, &pv)
                 }; // 🔴 lock dropped HERE
 
                 if send_json(&mut socket, &ServerMsg::State(final_state)).await.is_err() {
@@ -150,13 +155,10 @@ async fn handle_socket(mut socket: WebSocket) {
                                 board = Board::new();
 
                                 let searcher_cloned = searcher.clone();
-                                {
-                                    let mut s = searcher.lock().unwrap();
-                                    board.set_startpos(&s.nnue);
-                                }
-                                let text = serde_json::to_string(&ServerMsg::State(make_state(&mut board, &movegen, thinking, None, None))).unwrap();
+                                board.set_startpos();
+                                let text = serde_json::to_string(&ServerMsg::State(make_state(&mut board, &movegen, thinking, None, None, &[]))).unwrap();
                                 println!("SENT: {}", text);
-                                if send_json(&mut socket, &ServerMsg::State(make_state(&mut board, &movegen, false, None, None)))
+                                if send_json(&mut socket, &ServerMsg::State(make_state(&mut board, &movegen, false, None, None, &[])))
                                     .await
                                     .is_err()
                                 {
@@ -167,24 +169,24 @@ async fn handle_socket(mut socket: WebSocket) {
                                 if playerside == 1 {
                                     thinking = true;
                                     if send_json(&mut socket,
-                                        &ServerMsg::State(make_state(&mut board, &movegen, true, None, None))
+                                        &ServerMsg::State(make_state(&mut board, &movegen, true, None, None, &[]))
                                     ).await.is_err() {
                                         return;
                                     }
 
                                     let tx = engine_tx.clone();
-                                    let mut board_for_search = board.clone_position();
-                                    let depth: u8 = 5;
+                                    let mut board_for_search = board.clone();
+                                    let limits = SearchLimits::movetime(ENGINE_MOVETIME_MS);
                                     let searcher = searcher.clone();
 
                                     tokio::spawn(async move {
                                         let best = tokio::task::spawn_blocking(move || {
                                             let mg = MoveGenerator::new();
                                             let mut s = searcher.lock().unwrap();
-                                            s.search_root(&mut board_for_search, depth, &mg)
+                                            let (mv, score) = s.search_with_limits(&mut board_for_search, &limits, &mg);
+                                            (mv, score, s.pv.clone())
                                         }).await.unwrap();
-                                        let (mv, score) = best;
-                                        let _ = tx.send((mv, score));
+                                        let _ = tx.send(best);
 
                                     });
                                 }
@@ -195,13 +197,14 @@ async fn handle_socket(mut socket: WebSocket) {
                                     let _ = send_json(&mut socket, &ServerMsg::Error { message: "Engine is thinking".to_string() }).await;
                                     continue;
                                 }
-                                board = Board::new();
-                                let searcher = searcher.clone();
-                                {
-                                    let mut s = searcher.lock().unwrap();
-                                    board.from_fen(fen, &s.nnue);
-                                }
-                                if send_json(&mut socket, &ServerMsg::State(make_state(&mut board, &movegen, false, None, None)))
+                                board = match Board::try_from_fen(&fen) {
+                                    Ok(b) => b,
+                                    Err(e) => {
+                                        let _ = send_json(&mut socket, &ServerMsg::Error { message: format!("Invalid FEN: {e}") }).await;
+                                        continue;
+                                    }
+                                };
+                                if send_json(&mut socket, &ServerMsg::State(make_state(&mut board, &movegen, false, None, None, &[])))
                                     .await
                                     .is_err()
                                 {
@@ -223,16 +226,17 @@ async fn handle_socket(mut socket: WebSocket) {
                                 let Some(player_move) = legal.get(id as usize).copied() else {
                                     let _ = send_json(&mut socket, &ServerMsg::MoveResult { ok: false, reason: "Illegal move id".to_string() }).await;
                                     // Re-send state for UI consistency
-                                    let _ = send_json(&mut socket, &ServerMsg::State(make_state(&mut board, &movegen, false, None, None))).await;
+                                    let _ = send_json(&mut socket, &ServerMsg::State(make_state(&mut board, &movegen, false, None, None, &[]))).await;
                                     continue;
                                 };
 
                                 // Apply player move
                                 {
-                                    let mut s = searcher.lock().unwrap();
-                                    board.push(player_move, &movegen, &s.nnue);
-                                    let nn = s.nnue.eval_cp_like(&board);
-                                    eprintln!("NNUE eval after push: {}", nn);
+                                    let s = searcher.lock().unwrap();
+                                    board.push(player_move, &movegen);
+                                    if let Some(nnue) = s.net() {
+                                        eprintln!("NNUE eval after push: {}", nnue.eval_cp_like(&board));
+                                    }
                                 } // 🔴 lock dropped
 
                                 if send_json(&mut socket, &ServerMsg::MoveResult { ok: true, reason: String::new() })
@@ -244,30 +248,31 @@ async fn handle_socket(mut socket: WebSocket) {
 
                                 // Immediately send state with thinking=true (locks UI)
                                 thinking = true;
-                                if send_json(&mut socket, &ServerMsg::State(make_state(&mut board, &movegen, true, None, None)))
+                                if send_json(&mut socket, &ServerMsg::State(make_state(&mut board, &movegen, true, None, None, &[])))
                                     .await
                                     .is_err()
                                 {
                                     return;
                                 }
 
-                                // Spawn search to full depth (blocking)
+                                // Spawn a timed search (blocking)
                                 // IMPORTANT: we clone the board for search so we don't race the authoritative board.
                                 let tx = engine_tx.clone();
-                                let mut board_for_search = board.clone_position();
-                                let depth: u8 = 5;            // hardcode for now; add to protocol later
+                                let mut board_for_search = board.clone();
+                                let limits = SearchLimits::movetime(ENGINE_MOVETIME_MS);
                                 let searcher = searcher.clone();
                                 tokio::spawn(async move {
-                                    let best: Result<(EngineMove, i32), tokio::task::JoinError> =
+                                    let best: Result<(EngineMove, i32, Vec<EngineMove>), tokio::task::JoinError> =
                                         tokio::task::spawn_blocking(move || {
                                             let mg_local = MoveGenerator::new();
                                             let mut s = searcher.lock().unwrap();
-                                            s.search_root(&mut board_for_search, depth, &mg_local)
+                                            let (mv, score) = s.search_with_limits(&mut board_for_search, &limits, &mg_local);
+                                            (mv, score, s.pv.clone())
                                         })
                                         .await;
 
                                     match best {
-                                        Ok((best_move, best_score)) => { let _ = tx.send((best_move, best_score)); }
+                                        Ok(result) => { let _ = tx.send(result); }
                                         Err(e) => eprintln!("spawn_blocking join error: {e}"),
                                     }
                                 });
@@ -335,13 +340,13 @@ fn normalize_board_cells(cells: Vec<String>) -> Result<Vec<String>, String> {
         trimmed.len(),
         trimmed.get(0..std::cmp::min(trimmed.len(), 12))
     ))
-}fn make_state(board: &mut Board, movegen: &MoveGenerator, thinking: bool, eval: Option<i32>, best_move: Option<EngineMove>) -> State {
+}fn make_state(board: &mut Board, movegen: &MoveGenerator, thinking: bool, eval: Option<i32>, best_move: Option<EngineMove>, pv: &[EngineMove]) -> State {
     let raw = board.board_to_chars();
     let board_cells = normalize_board_cells(raw).expect("bad board_to_chars");
 
     let legal = if thinking {
         // Optional: you can send empty legal list while thinking to make UI simpler/safer.
-        Vec::new()
+        MoveList::new()
     } else {
         movegen.generate(board)
     };
@@ -378,6 +383,7 @@ fn normalize_board_cells(cells: Vec<String>) -> Result<Vec<String>, String> {
         thinking,
         eval,
         best_move: best_move.map(engine_move_to_ui),
+        pv: pv.iter().copied().map(engine_move_to_ui).collect(),
     }
 }
 async fn send_json(socket: &mut WebSocket, msg: &ServerMsg) -> Result<(), ()> {
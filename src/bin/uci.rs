@@ -0,0 +1,4 @@
+// UCI entry point: `cargo run --release --bin uci`, or point a GUI at the built binary.
+fn main() {
+    rustychess::uci::run();
+}
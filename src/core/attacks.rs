@@ -0,0 +1,68 @@
+use crate::core::movegen::MoveGenerator;
+use crate::core::piece::PieceIndex;
+use crate::core::Board;
+
+// ---- attack queries ----
+// Which pieces hit a square, for any occupancy. Piece sets always come from `board`,
+// so a caller that takes pieces off `occ` (SEE, legality tests) should mask the result
+// with `occ` itself.
+impl MoveGenerator {
+    /// Pieces of both colors attacking `sq` through occupancy `occ`.
+    #[inline(always)]
+    pub fn attackers_to(&self, board: &Board, sq: u8, occ: u64) -> u64 {
+        let p = &board.pieces;
+        (self.pawnattacks[1][sq as usize] & p[PieceIndex::P.index()])
+            | (self.pawnattacks[0][sq as usize] & p[PieceIndex::p.index()])
+            | (self.knight[sq as usize] & (p[PieceIndex::N.index()] | p[PieceIndex::n.index()]))
+            | (self.king[sq as usize] & (p[PieceIndex::K.index()] | p[PieceIndex::k.index()]))
+            | self.slider_attackers_to(board, sq, occ)
+    }
+
+    /// Pieces of `side` attacking `sq` through occupancy `occ`.
+    #[inline(always)]
+    pub fn attackers_to_by(&self, board: &Board, sq: u8, occ: u64, side: u8) -> u64 {
+        self.attackers_to(board, sq, occ) & board.playerpieces[side as usize]
+    }
+
+    /// Bishops, rooks and queens of both colors attacking `sq` through `occ`. Cheaper
+    /// than `attackers_to` when only lines have changed, e.g. after a capture in SEE.
+    #[inline(always)]
+    pub fn slider_attackers_to(&self, board: &Board, sq: u8, occ: u64) -> u64 {
+        let p = &board.pieces;
+        let queens = p[PieceIndex::Q.index()] | p[PieceIndex::q.index()];
+        let diag = p[PieceIndex::B.index()] | p[PieceIndex::b.index()] | queens;
+        let ortho = p[PieceIndex::R.index()] | p[PieceIndex::r.index()] | queens;
+        (self.bishop_attacks(sq, occ) & diag) | (self.rook_attacks(sq, occ) & ortho)
+    }
+
+    /// Squares a rook on `sq` would gain if those of `blockers` it hits first were lifted.
+    #[inline(always)]
+    pub fn xray_rook_attacks(&self, sq: u8, occ: u64, blockers: u64) -> u64 {
+        let attacks = self.rook_attacks(sq, occ);
+        attacks ^ self.rook_attacks(sq, occ ^ (blockers & attacks))
+    }
+
+    /// Squares a bishop on `sq` would gain if those of `blockers` it hits first were lifted.
+    #[inline(always)]
+    pub fn xray_bishop_attacks(&self, sq: u8, occ: u64, blockers: u64) -> u64 {
+        let attacks = self.bishop_attacks(sq, occ);
+        attacks ^ self.bishop_attacks(sq, occ ^ (blockers & attacks))
+    }
+
+    /// Sliders of both colors that hit `sq` through exactly one piece: batteries behind
+    /// a direct attacker as well as pinners and discovered attackers.
+    #[inline(always)]
+    pub fn xray_attackers_to(&self, board: &Board, sq: u8, occ: u64) -> u64 {
+        let p = &board.pieces;
+        let queens = p[PieceIndex::Q.index()] | p[PieceIndex::q.index()];
+        let diag = p[PieceIndex::B.index()] | p[PieceIndex::b.index()] | queens;
+        let ortho = p[PieceIndex::R.index()] | p[PieceIndex::r.index()] | queens;
+        (self.xray_bishop_attacks(sq, occ, occ) & diag) | (self.xray_rook_attacks(sq, occ, occ) & ortho)
+    }
+
+    /// Whether `c` lies on the line through `a` and `b`.
+    #[inline(always)]
+    pub fn aligned(&self, a: u8, b: u8, c: u8) -> bool {
+        self.line_between[a as usize][b as usize] & (1u64 << c) != 0
+    }
+}
@@ -1,4 +1,3 @@
-use crate::core::constlib;
 pub enum CastlingRights {
     NoCastling = 0b0000,
     WKingside = 0b0001,
@@ -9,27 +8,6 @@ pub enum CastlingRights {
 impl CastlingRights {
 }
 
-pub fn get_rights(sq:u8) -> u8 {
-    //this function takes a square and returns the appropriate castling right mask for that square
-    //if any square which the rook or the king starts on is interacted with, those rights must be updated
-    unsafe {
-        match sq {
-        //starting white rook squares
-        sq if sq == constlib::square_from_string("a1") => CastlingRights::WQueenside as u8,
-        sq if sq == constlib::square_from_string("h1") => CastlingRights::WKingside as u8,
-        
-        //starting black rook squares
-        sq if sq == constlib::square_from_string("a8") => CastlingRights::BQueenside as u8,
-        sq if sq == constlib::square_from_string("h8") => CastlingRights::BKingside as u8,
-        
-        //starting king squares
-        sq if sq == constlib::square_from_string("e1") => CastlingRights::WKingside as u8 | CastlingRights::WQueenside as u8,
-        sq if sq == constlib::square_from_string("e8") => CastlingRights::BKingside as u8 | CastlingRights::BQueenside as u8,
-
-        _ => CastlingRights::NoCastling as u8,
-    }
-    }
-}
 pub fn oppressed(rights: u8) -> bool {
     //if there are no rights, the player is oppressed
     rights == 0
@@ -70,19 +48,20 @@ pub fn bqueenside(rights: u8) -> bool {
         }
     }
 }
-pub fn get_castling_mask(string: &str) -> u8 {
-    let mut mask = 0;
-    for c in string.chars() {
-        let right = match c {
-            'K' => CastlingRights::WKingside,
-            'Q' => CastlingRights::WQueenside,
-            'k' => CastlingRights::BKingside,
-            'q' => CastlingRights::BQueenside,
-            '-' => CastlingRights::NoCastling,
-            _ => panic!("BAD CASTLING"),
-        };
-        mask |= right as u8;
-    }
-    return mask;
+/// Index of a single right into `Board::castling_rooks`: K, Q, k, q.
+pub fn right_index(color: u8, kingside: bool) -> usize {
+    2 * color as usize + if kingside { 0 } else { 1 }
+}
+
+/// Where king and rook end up: g/f files for king-side, c/d for queen-side,
+/// whatever files they started on (Chess960).
+pub fn castle_destinations(ksrc: u8, rsrc: u8) -> (u8, u8) {
+    let rank = ksrc & !7;
+    if rsrc > ksrc { (rank + 6, rank + 5) } else { (rank + 2, rank + 3) }
 }
 
+/// All squares from `a` to `b` inclusive, both on the same rank.
+pub fn rank_span(a: u8, b: u8) -> u64 {
+    let (lo, hi) = if a < b { (a, b) } else { (b, a) };
+    (u64::MAX >> (63 - hi)) & (u64::MAX << lo)
+}
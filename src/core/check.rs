@@ -0,0 +1,120 @@
+use crate::core::castling::castle_destinations;
+use crate::core::movegen::MoveGenerator;
+use crate::core::piece::PieceIndex;
+use crate::core::{Board, Move, PieceType};
+
+/// What the side to move needs to know to tell whether its moves give check.
+/// Compute it once per node with `MoveGenerator::check_info` when testing many moves.
+#[derive(Clone, Copy, Debug)]
+pub struct CheckInfo {
+    /// Square of the enemy king.
+    pub ksq: u8,
+    /// Squares from which a piece of each type (indexed by `PieceType as usize`)
+    /// would attack the enemy king.
+    pub check_squares: [u64; 7],
+    /// Our pieces that are the only thing between one of our sliders and the enemy king.
+    pub discoverers: u64,
+}
+
+impl MoveGenerator {
+    pub fn check_info(&self, board: &Board) -> CheckInfo {
+        let us = board.turn as usize;
+        let them = us ^ 1;
+        let ksq = board.pieces[6 * them + PieceIndex::K.index()].trailing_zeros() as u8;
+        let occ = board.occupied;
+        let diag = self.bishop_attacks(ksq, occ);
+        let ortho = self.rook_attacks(ksq, occ);
+
+        let mut check_squares = [0u64; 7];
+        check_squares[PieceType::P as usize] = self.pawnattacks[them][ksq as usize];
+        check_squares[PieceType::N as usize] = self.knight[ksq as usize];
+        check_squares[PieceType::B as usize] = diag;
+        check_squares[PieceType::R as usize] = ortho;
+        check_squares[PieceType::Q as usize] = diag | ortho;
+
+        let p = &board.pieces;
+        let sliders = p[6 * us + PieceIndex::B.index()] | p[6 * us + PieceIndex::R.index()] | p[6 * us + PieceIndex::Q.index()];
+        let discoverers = self.slider_blockers(board, sliders, ksq) & board.playerpieces[us];
+
+        CheckInfo { ksq, check_squares, discoverers }
+    }
+
+    /// Whether `mv` (legal for the side to move) checks the enemy king, without playing it.
+    /// Covers direct and discovered checks, promotions, en passant and castling.
+    pub fn gives_check(&self, board: &Board, mv: Move) -> bool {
+        self.gives_check_with(board, mv, &self.check_info(board))
+    }
+
+    /// `gives_check` with the node's `CheckInfo` computed by the caller.
+    pub fn gives_check_with(&self, board: &Board, mv: Move, ci: &CheckInfo) -> bool {
+        let from = mv.getSrc();
+        let to = mv.getDst();
+        let to_bb = 1u64 << to;
+        let us = board.turn as usize;
+
+        if mv.iscastle() {
+            // only the rook can check, directly or by opening a line for another slider
+            let (kdst, rdst) = castle_destinations(from, to);
+            let occ = (board.occupied & !(1u64 << from) & !to_bb) | (1u64 << kdst) | (1u64 << rdst);
+            let rooks = (board.pieces[6 * us + PieceIndex::R.index()] & !to_bb) | (1u64 << rdst);
+            return self.slider_hits(board, ci.ksq, occ, rooks);
+        }
+
+        let pt = board.piecelocs.piece_at(from).get_piece_type();
+        if ci.check_squares[pt as usize] & to_bb != 0 {
+            return true;
+        }
+        // a discoverer that stays on the line to the king still blocks it
+        if ci.discoverers & (1u64 << from) != 0 && !self.aligned(from, ci.ksq, to) {
+            return true;
+        }
+
+        if mv.isprom() {
+            let occ = board.occupied & !(1u64 << from);
+            return match mv.prompiece() {
+                PieceType::N => self.knight[to as usize] & (1u64 << ci.ksq) != 0,
+                PieceType::B => self.bishop_attacks(to, occ) & (1u64 << ci.ksq) != 0,
+                PieceType::R => self.rook_attacks(to, occ) & (1u64 << ci.ksq) != 0,
+                _ => (self.bishop_attacks(to, occ) | self.rook_attacks(to, occ)) & (1u64 << ci.ksq) != 0,
+            };
+        }
+        if mv.isep() {
+            // two pieces leave the board's lines at once, which the discoverers do not capture
+            let cap_sq = if us == 0 { to - 8 } else { to + 8 };
+            let occ = (board.occupied & !(1u64 << from) & !(1u64 << cap_sq)) | to_bb;
+            return self.slider_hits(board, ci.ksq, occ, board.pieces[6 * us + PieceIndex::R.index()]);
+        }
+        false
+    }
+
+    // Whether a bishop, queen or one of `rooks` of the side to move attacks `ksq` through `occ`.
+    #[inline(always)]
+    fn slider_hits(&self, board: &Board, ksq: u8, occ: u64, rooks: u64) -> bool {
+        let us = board.turn as usize;
+        let queens = board.pieces[6 * us + PieceIndex::Q.index()];
+        let diag = board.pieces[6 * us + PieceIndex::B.index()] | queens;
+        (self.bishop_attacks(ksq, occ) & diag) | (self.rook_attacks(ksq, occ) & (rooks | queens)) != 0
+    }
+
+    /// Pieces of either color that stand alone between `ksq` and one of `sliders`
+    /// aimed along their own lines at it.
+    pub fn slider_blockers(&self, board: &Board, sliders: u64, ksq: u8) -> u64 {
+        let p = &board.pieces;
+        let queens = p[PieceIndex::Q.index()] | p[PieceIndex::q.index()];
+        let rooks = p[PieceIndex::R.index()] | p[PieceIndex::r.index()] | queens;
+        let bishops = p[PieceIndex::B.index()] | p[PieceIndex::b.index()] | queens;
+
+        let mut blockers = 0;
+        let mut snipers = sliders & ((self.rook[ksq as usize] & rooks) | (self.bishop[ksq as usize] & bishops));
+        let occ = board.occupied & !snipers;
+        while snipers != 0 {
+            let sq = snipers.trailing_zeros() as u8;
+            snipers &= snipers - 1;
+            let b = self.between[sq as usize][ksq as usize] & occ;
+            if b != 0 && b & (b - 1) == 0 {
+                blockers |= b;
+            }
+        }
+        blockers
+    }
+}
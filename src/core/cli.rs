@@ -7,7 +7,6 @@ use crate::core::r#move::Move;
 use crate::core::piece::PieceType;
 use crate::core::Piece;
 use crate::search;
-use crate::evaluate::nnue::Nnue;
 /// Convert UCI string (e.g., "e2e4", "e7e8q") into a Move
 pub fn uci_to_move(board: &mut Board, gen: &MoveGenerator, uci: &str) -> Option<Move> {
     let mut uci = uci.trim().to_ascii_lowercase();
@@ -28,12 +27,9 @@ pub fn uci_to_move(board: &mut Board, gen: &MoveGenerator, uci: &str) -> Option<
         None
     };
 
-    let mut moves: Vec<Move> = Vec::new();
-    // Whatever your generator entrypoint is called; adjust if needed:
-    // gen.generate(board, &mut moves, /*evasions?*/ false);
-    moves = gen.generate(board);
+    let moves = gen.generate(board);
 
-    for mv in moves {
+    for &mv in moves.iter() {
         if mv.getSrc() == src && mv.getDst() == dst {
             match promo {
                 None => {
@@ -45,11 +41,14 @@ pub fn uci_to_move(board: &mut Board, gen: &MoveGenerator, uci: &str) -> Option<
             }
         }
     }
-    None
+    // castles are stored king-takes-rook, matched above; also accept the usual
+    // king-destination form (e1g1). Checked last, since in Chess960 that can also
+    // be a plain king move.
+    moves.iter().copied().find(|mv| mv.iscastle() && mv.to_uci() == uci)
 }
 
 /// Interactive command line tester for the chess engine
-pub fn interactive_cli(board: &mut Board, generator: &MoveGenerator, nnue: &Nnue) {
+pub fn interactive_cli(board: &mut Board, generator: &MoveGenerator) {
     let mut input = String::new();
     loop {
         board.print();
@@ -68,7 +67,7 @@ pub fn interactive_cli(board: &mut Board, generator: &MoveGenerator, nnue: &Nnue
             None => { println!("Invalid move format."); continue; }
         };
 
-        board.push(mv, generator, nnue);
+        board.push(mv, generator);
         println!("Move applied: {}{}", &uci_from_square(mv.getSrc()), &uci_from_square(mv.getDst()));
 
         // Generate legal moves
@@ -78,7 +77,9 @@ pub fn interactive_cli(board: &mut Board, generator: &MoveGenerator, nnue: &Nnue
         }
         let mut searcher = search::Search::new(false);
         let bm = searcher.search_iterative(board,7, generator).0;
-        board.push(bm, generator, nnue);
+        searcher.print_stats();
+        println!("PV: {}", search::Search::pv_to_uci(&searcher.pv, searcher.chess960));
+        board.push(bm, generator);
         println!("Move applied: ");
         bm.print();
         println!("Legal moves: {} total", moves.len());
@@ -88,7 +89,7 @@ pub fn interactive_cli(board: &mut Board, generator: &MoveGenerator, nnue: &Nnue
 
 
 /// Interactive command line tester for the chess engine
-pub fn interactive_cli_test(board: &mut Board, generator: &MoveGenerator, nnue: &Nnue) {
+pub fn interactive_cli_test(board: &mut Board, generator: &MoveGenerator) {
     let mut input = String::new();
     loop {
         board.print();
@@ -107,7 +108,7 @@ pub fn interactive_cli_test(board: &mut Board, generator: &MoveGenerator, nnue:
             None => { println!("Invalid move format."); continue; }
         };
 
-        board.push(mv, generator, nnue);
+        board.push(mv, generator);
         println!("Move applied: {}{}", &uci_from_square(mv.getSrc()), &uci_from_square(mv.getDst()));
 
         // Generate legal moves
@@ -2,7 +2,7 @@
 pub use crate::core::movegen::MoveGenerator;
 pub use crate::core::Board;
 pub use crate::core::Move;
-use crate::evaluate::nnue::Nnue;
+use crate::core::magic;
 
 /// Direction of going north on a chessboard.
 pub const north: i8 = 8;
@@ -58,7 +58,7 @@ pub const fn initRankMaskLookup() -> [u64; 8] {
 }
 
 pub const fn initFileMaskLookup() -> [u64; 8] {
-    let fileA = 0b0101010101010101;
+    let fileA = 0x0101010101010101;
     let mut arr = [0; 8];
     let mut i = 0;
     while i < 8 {
@@ -75,21 +75,6 @@ pub const notAFile:u64 = 0xfefefefefefefefe; // ~0x0101010101010101
 pub const notHFile:u64 = 0x7f7f7f7f7f7f7f7f; // ~0x8080808080808080
 
 
-pub fn perft(board: &mut Board, depth: u8, mg: &MoveGenerator, nnue: &Nnue) -> u64 {
-  let mut ct = 0;
-  if depth == 0 {
-      return 1
-  }
-  let ml = mg.generate(board);
-  for bm in ml {
-      board.push(bm,mg, nnue);
-      let moves = perft(board, depth - 1, mg, nnue);
-      ct += moves;
-      board.pop(mg, nnue);
-  }
-  ct
-}
-
 pub fn get_rank(square: u8) -> u8 {
   // For a square in the range 0-63, divide by 8 and add 1 to get the rank (1-8)
   (square / 8) + 1
@@ -144,8 +129,8 @@ pub fn print_bitboard(bitboard: u64) {
 pub fn square_from_string(square_str:&str) -> u8 {
   let mut square_value: u8 = 64; // Initialize with an invalid value
   if square_str.len() == 2 {
-      let file = square_str.chars().next().unwrap();
-      let rank = square_str.chars().nth(1).unwrap();
+      let file = square_str.as_bytes()[0] as char;
+      let rank = square_str.as_bytes()[1] as char;
 
       let file_value = match file {
           'a' => 0,
@@ -180,8 +165,8 @@ pub fn squarebb_from_string(square_str: &str) -> u64 {
   let mut square_value: u8 = 64; // Initialize with an invalid value
 
   if square_str.len() == 2 {
-      let file = square_str.chars().next().unwrap();
-      let rank = square_str.chars().nth(1).unwrap();
+      let file = square_str.as_bytes()[0] as char;
+      let rank = square_str.as_bytes()[1] as char;
 
       let file_value = match file {
           'a' => 0,
@@ -225,8 +210,19 @@ pub fn squaretouci(square: u8) -> String {
   format!("{}{}", file as char, rank as char)
 }
 
+// sliding attacks, answered from the magic tables (see core::magic)
+#[inline(always)]
+pub fn compute_bishop(sq:i8, blockers:u64) -> u64 {
+  magic::bishop_attacks(sq as u8, blockers)
+}
+
+#[inline(always)]
+pub fn compute_rook(sq:i8, blockers:u64) -> u64 {
+  magic::rook_attacks(sq as u8, blockers)
+}
 
-pub fn compute_bishop(sq:i8,blockers:u64) -> u64{
+// reference ray walkers, used to build and check the magic tables
+pub fn ray_bishop(sq:i8,blockers:u64) -> u64{
       
   let mut attacks = 0;
   //current square
@@ -287,8 +283,7 @@ pub fn compute_bishop(sq:i8,blockers:u64) -> u64{
   return attacks
 }
 
-#[inline(always)]
-pub fn compute_rook(sq:i8, blockers:u64)->u64{
+pub fn ray_rook(sq:i8, blockers:u64)->u64{
   let mut attacks = 0;
   let mut currpos: i8;
   for ortho in 0..4 {
@@ -0,0 +1,283 @@
+use std::fmt;
+
+use crate::core::castling;
+use crate::core::constlib;
+use crate::core::movegen::MoveGenerator;
+use crate::core::{Board, Piece, PieceIndex, PieceType};
+
+/// Everything that can be wrong with a FEN string, as reported by `Board::try_from_fen`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenError {
+    /// FEN needs 4 to 6 space separated fields (the move counters are optional).
+    FieldCount(usize),
+    /// The placement field must describe exactly 8 ranks.
+    RankCount(usize),
+    /// A rank (1..=8) does not add up to 8 files.
+    RankLength { rank: u8, files: usize },
+    UnknownPiece(char),
+    SideToMove(String),
+    CastlingField(String),
+    EpField(String),
+    HalfmoveClock(String),
+    FullmoveNumber(String),
+    /// Each side needs exactly one king; `count` is how many were found.
+    KingCount { color: u8, count: u32 },
+    PawnOnBackRank(u8),
+    /// The side that just moved left its king in check.
+    SideNotToMoveInCheck,
+    /// A castling right with the king off its back rank or no rook to castle with.
+    ImpossibleCastling(char),
+    /// An EP square that no double pawn push could have produced.
+    ImpossibleEpSquare(u8),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::FieldCount(n) => write!(f, "expected 4 to 6 fields, found {}", n),
+            FenError::RankCount(n) => write!(f, "expected 8 ranks, found {}", n),
+            FenError::RankLength { rank, files } => {
+                write!(f, "rank {} describes {} files instead of 8", rank, files)
+            }
+            FenError::UnknownPiece(c) => write!(f, "unknown piece character '{}'", c),
+            FenError::SideToMove(s) => write!(f, "side to move must be 'w' or 'b', found '{}'", s),
+            FenError::CastlingField(s) => write!(f, "bad castling field '{}'", s),
+            FenError::EpField(s) => write!(f, "bad en passant field '{}'", s),
+            FenError::HalfmoveClock(s) => write!(f, "bad halfmove clock '{}'", s),
+            FenError::FullmoveNumber(s) => write!(f, "bad fullmove number '{}'", s),
+            FenError::KingCount { color, count } => write!(
+                f,
+                "{} has {} kings",
+                if *color == 0 { "white" } else { "black" },
+                count
+            ),
+            FenError::PawnOnBackRank(sq) => {
+                write!(f, "pawn on back rank at {}", constlib::squaretouci(*sq))
+            }
+            FenError::SideNotToMoveInCheck => write!(f, "side not to move is in check"),
+            FenError::ImpossibleCastling(c) => {
+                write!(f, "castling right '{}' without a king and matching rook on the back rank", c)
+            }
+            FenError::ImpossibleEpSquare(sq) => {
+                write!(f, "impossible en passant square {}", constlib::squaretouci(*sq))
+            }
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+impl std::str::FromStr for Board {
+    type Err = FenError;
+
+    fn from_str(fen: &str) -> Result<Self, Self::Err> {
+        Board::try_from_fen(fen)
+    }
+}
+
+/// Formats the board as FEN.
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_fen())
+    }
+}
+
+impl Board {
+    /// Parses a FEN string, rejecting anything that does not describe a reachable-looking
+    /// position. The returned board has caches and hash set up.
+    pub fn try_from_fen(fen: &str) -> Result<Board, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() < 4 || fields.len() > 6 {
+            return Err(FenError::FieldCount(fields.len()));
+        }
+
+        let mut board = Board::new();
+
+        // ---- piece placement ----
+        let ranks: Vec<&str> = fields[0].split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::RankCount(ranks.len()));
+        }
+        for (i, rank_str) in ranks.iter().enumerate() {
+            let rank = 7 - i;
+            let mut file = 0usize;
+            for c in rank_str.chars() {
+                if let Some(d) = c.to_digit(10) {
+                    if d == 0 || d > 8 {
+                        return Err(FenError::RankLength { rank: rank as u8 + 1, files: file + d as usize });
+                    }
+                    file += d as usize;
+                } else {
+                    if !"pnbrqkPNBRQK".contains(c) {
+                        return Err(FenError::UnknownPiece(c));
+                    }
+                    if file < 8 {
+                        board.put_piece(c, rank, file);
+                    }
+                    file += 1;
+                }
+                if file > 8 {
+                    return Err(FenError::RankLength { rank: rank as u8 + 1, files: file });
+                }
+            }
+            if file != 8 {
+                return Err(FenError::RankLength { rank: rank as u8 + 1, files: file });
+            }
+        }
+
+        // ---- side to move ----
+        board.turn = match fields[1] {
+            "w" => 0,
+            "b" => 1,
+            other => return Err(FenError::SideToMove(other.to_string())),
+        };
+
+        // ---- castling: KQkq, X-FEN or Shredder-FEN file letters ----
+        let castling_field = fields[2];
+        if castling_field.is_empty()
+            || (castling_field != "-"
+                && !castling_field.chars().all(|c| "KQkq".contains(c) || matches!(c.to_ascii_lowercase(), 'a'..='h')))
+            // each right at most once
+            || castling_field.chars().enumerate().any(|(i, c)| castling_field.chars().skip(i + 1).any(|d| d == c))
+        {
+            return Err(FenError::CastlingField(castling_field.to_string()));
+        }
+
+        // ---- en passant ----
+        // matched on bytes: anything but a plain ASCII square is an error, never a panic
+        board.ep_square = match fields[3].as_bytes() {
+            b"-" => 64,
+            &[f @ b'a'..=b'h', r @ b'1'..=b'8'] => (r - b'1') * 8 + (f - b'a'),
+            _ => return Err(FenError::EpField(fields[3].to_string())),
+        };
+
+        // ---- move counters ----
+        if let Some(hm) = fields.get(4) {
+            board.halfmove_clock = hm.parse().map_err(|_| FenError::HalfmoveClock(hm.to_string()))?;
+        }
+        if let Some(fm) = fields.get(5) {
+            board.fullmove_number = fm.parse().map_err(|_| FenError::FullmoveNumber(fm.to_string()))?;
+            if board.fullmove_number == 0 {
+                return Err(FenError::FullmoveNumber(fm.to_string()));
+            }
+        }
+
+        let mg = MoveGenerator::shared();
+        board.validate(mg)?;
+        if castling_field != "-" {
+            for c in castling_field.chars() {
+                board.add_castling_right(c, castling_field)?;
+            }
+        }
+
+        // initialize caches and hash
+        let pininfo = mg.getpinned(&board);
+        board.pinned = pininfo.0;
+        board.pinners = pininfo.1;
+        board.attacked[board.turn as usize] = mg.makeattackedmask(&board, board.turn, board.occupied);
+        board.hash = Self::compute_hash(&board);
+        Ok(board)
+    }
+
+    // Resolves one castling character to a rook. K/Q pick the outermost rook on that side
+    // of the king (X-FEN); a file letter names the rook directly (Shredder-FEN).
+    fn add_castling_right(&mut self, c: char, field: &str) -> Result<(), FenError> {
+        let color = if c.is_ascii_uppercase() { 0u8 } else { 1 };
+        let back_rank = constlib::rankmasks[if color == 0 { 0 } else { 7 }];
+        let king_bb = self.pieces[6 * color as usize + PieceIndex::K.index()];
+        if king_bb & back_rank == 0 {
+            return Err(FenError::ImpossibleCastling(c));
+        }
+        let ksq = king_bb.trailing_zeros() as u8;
+        let rooks = self.pieces[6 * color as usize + PieceIndex::R.index()] & back_rank;
+        let above_king = rooks & !(u64::MAX >> (63 - ksq));
+        let below_king = rooks & ((1u64 << ksq) - 1);
+
+        let rsq = match c.to_ascii_lowercase() {
+            'k' if above_king != 0 => 63 - above_king.leading_zeros() as u8,
+            'q' if below_king != 0 => below_king.trailing_zeros() as u8,
+            f @ 'a'..='h' if rooks & (1u64 << ((ksq & !7) + (f as u8 - b'a'))) != 0 => (ksq & !7) + (f as u8 - b'a'),
+            _ => return Err(FenError::ImpossibleCastling(c)),
+        };
+
+        let idx = castling::right_index(color, rsq > ksq);
+        if self.castling_rights & (1 << idx) != 0 && self.castling_rooks[idx] != rsq {
+            // e.g. "KH" with two different king-side rooks
+            return Err(FenError::CastlingField(field.to_string()));
+        }
+        self.castling_rights |= 1 << idx;
+        self.castling_rooks[idx] = rsq;
+        self.castling_mask[ksq as usize] |= 1 << idx;
+        self.castling_mask[rsq as usize] |= 1 << idx;
+        Ok(())
+    }
+
+    /// The FEN castling field in X-FEN form: KQkq whenever the outermost rook is meant,
+    /// the rook's file letter otherwise. Standard positions come out as plain KQkq.
+    pub fn castling_field(&self) -> String {
+        let mut s = String::new();
+        for idx in 0..4 {
+            if self.castling_rights & (1 << idx) == 0 {
+                continue;
+            }
+            let color = idx / 2;
+            let kingside = idx % 2 == 0;
+            let rsq = self.castling_rooks[idx];
+            // any rook of ours further out on the same side hides this one from K/Q
+            let outer = self.pieces[6 * color + PieceIndex::R.index()]
+                & castling::rank_span(rsq, if kingside { rsq | 7 } else { rsq & !7 })
+                & !(1u64 << rsq);
+            let ch = if outer == 0 {
+                if kingside { 'k' } else { 'q' }
+            } else {
+                (b'a' + rsq % 8) as char
+            };
+            s.push(if color == 0 { ch.to_ascii_uppercase() } else { ch });
+        }
+        if s.is_empty() {
+            s.push('-');
+        }
+        s
+    }
+
+    /// Semantic checks that need the whole position parsed first.
+    fn validate(&self, mg: &MoveGenerator) -> Result<(), FenError> {
+        for color in 0..2u8 {
+            let count = self.pieces[6 * color as usize + PieceIndex::K.index()].count_ones();
+            if count != 1 {
+                return Err(FenError::KingCount { color, count });
+            }
+        }
+
+        let pawns = self.pieces[PieceIndex::P.index()] | self.pieces[PieceIndex::p.index()];
+        let back_ranks = constlib::rankmasks[0] | constlib::rankmasks[7];
+        if pawns & back_ranks != 0 {
+            return Err(FenError::PawnOnBackRank((pawns & back_ranks).trailing_zeros() as u8));
+        }
+
+        let them = self.turn ^ 1;
+        let their_king = self.pieces[6 * them as usize + PieceIndex::K.index()].trailing_zeros() as u8;
+        if mg.is_square_attacked_by(self, self.occupied, self.turn, their_king) {
+            return Err(FenError::SideNotToMoveInCheck);
+        }
+
+        if self.ep_square != 64 {
+            // white to move => black just pushed, EP square on rank 6 with the pawn on rank 5
+            let ep = self.ep_square;
+            let (ep_rank, pawn_sq, origin_sq) = if self.turn == 0 {
+                (5, ep.wrapping_sub(8), ep.wrapping_add(8))
+            } else {
+                (2, ep.wrapping_add(8), ep.wrapping_sub(8))
+            };
+            let pusher = Piece::make(self.turn ^ 1, PieceType::P);
+            if ep / 8 != ep_rank
+                || self.piecelocs.piece_at(ep) != Piece::None
+                || self.piecelocs.piece_at(origin_sq) != Piece::None
+                || self.piecelocs.piece_at(pawn_sq) != pusher
+            {
+                return Err(FenError::ImpossibleEpSquare(ep));
+            }
+        }
+        Ok(())
+    }
+}
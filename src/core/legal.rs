@@ -0,0 +1,113 @@
+use crate::core::movegen::MoveGenerator;
+use crate::core::piece::PieceIndex;
+use crate::core::{Board, Move, MoveList, Piece, PieceType};
+
+// ---- single-move validation ----
+// For moves that did not come from the generator: TT moves (a key collision can hand
+// back a move from another position), killers and external input. Each check looks at
+// the one move only, never at the full move list.
+impl MoveGenerator {
+    /// Whether `mv` is a well-formed move of the side to move in this position: right
+    /// piece, consistent flags, reachable destination. Whether it leaves the king in
+    /// check is `is_legal`'s job; castling is checked in full here.
+    pub fn is_pseudo_legal(&self, board: &Board, mv: Move) -> bool {
+        if mv.is_null() || mv.flag() == Move::ILLEGAL_FLAG_1 || mv.flag() == Move::ILLEGAL_FLAG_2 {
+            return false;
+        }
+        let us = board.turn;
+        let them = us ^ 1;
+        let src = mv.getSrc();
+        let dst = mv.getDst();
+        let piece = board.piecelocs.piece_at(src);
+        if piece == Piece::None || piece.get_color() != us {
+            return false;
+        }
+        let pt = piece.get_piece_type();
+
+        if mv.iscastle() {
+            if pt != PieceType::K {
+                return false;
+            }
+            // rare enough to just ask the castling generator
+            let mut castles = MoveList::new();
+            self.generatecastling(board, &mut castles, self.enemy_attacks(board));
+            return castles.contains(&mv);
+        }
+
+        let dst_bb = 1u64 << dst;
+        if board.playerpieces[us as usize] & dst_bb != 0 {
+            return false;
+        }
+        if mv.isep() {
+            return pt == PieceType::P && dst == board.ep_square && self.pawnattacks[us as usize][src as usize] & dst_bb != 0;
+        }
+        let enemy_on_dst = board.playerpieces[them as usize] & dst_bb != 0;
+        if enemy_on_dst != mv.iscapture() {
+            return false;
+        }
+
+        match pt {
+            PieceType::P => {
+                let last_rank = if us == 0 { dst / 8 == 7 } else { dst / 8 == 0 };
+                if last_rank != mv.isprom() {
+                    return false;
+                }
+                if mv.iscapture() {
+                    self.pawnattacks[us as usize][src as usize] & dst_bb != 0
+                } else if mv.isdoublepawn() {
+                    let (start_rank, mid) = if us == 0 { (1, src + 8) } else { (6, src.wrapping_sub(8)) };
+                    src / 8 == start_rank
+                        && dst == if us == 0 { src + 16 } else { src.wrapping_sub(16) }
+                        && board.occupied & ((1u64 << mid) | dst_bb) == 0
+                } else {
+                    self.pawnmoves[us as usize][src as usize] & dst_bb != 0
+                }
+            }
+            _ => {
+                if mv.isprom() || mv.isdoublepawn() {
+                    return false;
+                }
+                let attacks = match pt {
+                    PieceType::N => self.knight[src as usize],
+                    PieceType::B => self.bishop_attacks(src, board.occupied),
+                    PieceType::R => self.rook_attacks(src, board.occupied),
+                    PieceType::Q => self.bishop_attacks(src, board.occupied) | self.rook_attacks(src, board.occupied),
+                    _ => self.king[src as usize],
+                };
+                attacks & dst_bb != 0
+            }
+        }
+    }
+
+    /// Whether a pseudo-legal `mv` leaves the mover's king safe. Handles pins, check
+    /// evasions and en passant discovering a rank attack in one occupancy test.
+    pub fn is_legal(&self, board: &Board, mv: Move) -> bool {
+        if mv.iscastle() {
+            return true;
+        }
+        let us = board.turn as usize;
+        let src = mv.getSrc();
+        let dst = mv.getDst();
+        let src_bb = 1u64 << src;
+        let dst_bb = 1u64 << dst;
+        let enemies = board.playerpieces[us ^ 1];
+        let king_bb = board.pieces[6 * us + PieceIndex::K.index()];
+
+        if king_bb & src_bb != 0 {
+            // a piece captured on dst no longer attacks it
+            let occ = (board.occupied & !src_bb) | dst_bb;
+            return self.attackers_to(board, dst, occ) & enemies & !dst_bb == 0;
+        }
+
+        let captured_bb = if mv.isep() {
+            1u64 << if us == 0 { dst - 8 } else { dst + 8 }
+        } else if mv.iscapture() {
+            dst_bb
+        } else {
+            0
+        };
+        let occ = (board.occupied & !src_bb & !captured_bb) | dst_bb;
+        let ksq = king_bb.trailing_zeros() as u8;
+        self.attackers_to(board, ksq, occ) & enemies & !captured_bb == 0
+    }
+}
@@ -0,0 +1,151 @@
+// Magic bitboard lookup for sliding pieces.
+//
+// For every square we keep the "relevant occupancy" mask (the ray squares minus the board
+// edge, whose occupancy never changes the result), a magic multiplier and a shift. Multiplying
+// the masked occupancy by the magic and shifting gives a perfect-enough hash into a per-square
+// slice of one shared attack table. Magics are searched once at startup with a fixed seed, and
+// every table entry is produced by the ray walkers in constlib, so lookups are bit-identical to
+// `ray_bishop`/`ray_rook` by construction.
+use std::sync::OnceLock;
+
+use crate::core::constlib;
+
+const RANK_1: u64 = 0x0000_0000_0000_00FF;
+const RANK_8: u64 = 0xFF00_0000_0000_0000;
+const FILE_A: u64 = 0x0101_0101_0101_0101;
+const FILE_H: u64 = 0x8080_8080_8080_8080;
+
+#[derive(Clone, Copy, Default)]
+struct Magic {
+    mask: u64,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+impl Magic {
+    #[inline(always)]
+    fn index(&self, occ: u64) -> usize {
+        self.offset + ((occ & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+struct MagicTables {
+    bishop: [Magic; 64],
+    rook: [Magic; 64],
+    attacks: Vec<u64>,
+}
+
+static TABLES: OnceLock<MagicTables> = OnceLock::new();
+
+#[inline(always)]
+fn tables() -> &'static MagicTables {
+    TABLES.get_or_init(MagicTables::init)
+}
+
+/// Builds the tables now instead of on the first lookup. Called by `MoveGenerator::new`.
+pub fn init() {
+    tables();
+}
+
+#[inline(always)]
+pub fn bishop_attacks(sq: u8, occ: u64) -> u64 {
+    let t = tables();
+    t.attacks[t.bishop[sq as usize].index(occ)]
+}
+
+#[inline(always)]
+pub fn rook_attacks(sq: u8, occ: u64) -> u64 {
+    let t = tables();
+    t.attacks[t.rook[sq as usize].index(occ)]
+}
+
+fn bishop_mask(sq: u8) -> u64 {
+    constlib::ray_bishop(sq as i8, 0) & !(RANK_1 | RANK_8 | FILE_A | FILE_H)
+}
+
+fn rook_mask(sq: u8) -> u64 {
+    let rays = constlib::ray_rook(sq as i8, 0);
+    let rank = RANK_1 << (8 * (sq / 8));
+    let file = FILE_A << (sq % 8);
+    (rays & rank & !(FILE_A | FILE_H)) | (rays & file & !(RANK_1 | RANK_8))
+}
+
+// xorshift64*, deterministic so every run builds the same tables
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+    // magics work best with few bits set
+    fn sparse(&mut self) -> u64 {
+        self.next() & self.next() & self.next()
+    }
+}
+
+impl MagicTables {
+    fn init() -> Self {
+        let mut tables = MagicTables { bishop: [Magic::default(); 64], rook: [Magic::default(); 64], attacks: Vec::new() };
+        let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+        for sq in 0..64u8 {
+            tables.bishop[sq as usize] =
+                find_magic(sq, bishop_mask(sq), constlib::ray_bishop, &mut rng, &mut tables.attacks);
+            tables.rook[sq as usize] = find_magic(sq, rook_mask(sq), constlib::ray_rook, &mut rng, &mut tables.attacks);
+        }
+        tables
+    }
+}
+
+// Finds a collision-free magic for one square and appends its attack slice to `table`.
+fn find_magic(sq: u8, mask: u64, ray: fn(i8, u64) -> u64, rng: &mut Rng, table: &mut Vec<u64>) -> Magic {
+    let bits = mask.count_ones();
+    let size = 1usize << bits;
+
+    // every subset of the mask (carry-rippler) with its reference attack set
+    let mut occupancies = Vec::with_capacity(size);
+    let mut reference = Vec::with_capacity(size);
+    let mut subset = 0u64;
+    loop {
+        occupancies.push(subset);
+        reference.push(ray(sq as i8, subset));
+        subset = subset.wrapping_sub(mask) & mask;
+        if subset == 0 {
+            break;
+        }
+    }
+
+    let offset = table.len();
+    table.resize(offset + size, 0);
+    let slice = &mut table[offset..];
+    // epoch[i] == attempt means slice[i] was written during the current attempt
+    let mut epoch = vec![0u32; size];
+    let mut attempt = 0u32;
+
+    loop {
+        let magic = rng.sparse();
+        if (mask.wrapping_mul(magic) & 0xFF00_0000_0000_0000).count_ones() < 6 {
+            continue;
+        }
+        attempt += 1;
+        let candidate = Magic { mask, magic, shift: 64 - bits, offset: 0 };
+
+        let mut ok = true;
+        for (&occ, &att) in occupancies.iter().zip(reference.iter()) {
+            let idx = candidate.index(occ);
+            if epoch[idx] != attempt {
+                epoch[idx] = attempt;
+                slice[idx] = att;
+            } else if slice[idx] != att {
+                ok = false;
+                break;
+            }
+        }
+        if ok {
+            return Magic { offset, ..candidate };
+        }
+    }
+}
@@ -6,6 +6,15 @@ pub mod r#move;
 pub mod state;
 pub mod cli;
 pub mod zobrist;
+pub mod magic;
+pub mod movelist;
+pub mod fen;
+pub mod san;
+pub mod perft;
+pub mod see;
+pub mod check;
+pub mod legal;
+pub mod attacks;
 #[cfg(test)]
 pub mod tests;
 
@@ -14,17 +23,19 @@ pub use piece::PieceLocations;
 pub use piece::PieceType;
 pub use piece::PieceIndex;
 pub use r#move::Move;
+pub use movelist::MoveList;
 pub use castling::CastlingRights;
 pub use state::Undo;
+pub use fen::FenError;
+pub use san::SanError;
 
-pub use crate::evaluate::nnue::Nnue;
-use crate::evaluate::nnue::{nnue_add_piece,nnue_sub_piece};
-use crate::core::zobrist::{Z_PIECE_SQ, Z_SIDE, Z_CASTLING, Z_EP_FILE};
+use crate::core::zobrist::{Z_PIECE_SQ, Z_SIDE, Z_CASTLING, Z_CASTLING_ROOK, Z_EP_FILE};
 use crate::perf;
 use std::time::Instant;
 
-use crate::evaluate::nnue::nnue_move_piece;
 // a struct defining the physical aspects of the board
+/// `clone()` copies the move history too, so repetition detection and `pop` work on the copy.
+#[derive(Clone)]
 pub struct Board {
     pub occupied: u64,
     pub pieces: [u64; 12],
@@ -34,8 +45,17 @@ pub struct Board {
 
     // ---- true game state (previously stored in BoardState/Arc chain) ----
     pub castling_rights: u8,
+    /// home square of the rook for each right (K, Q, k, q), 64 when unused.
+    /// Fixed for the whole game, which is what makes Chess960 castling work.
+    pub castling_rooks: [u8; 4],
+    /// rights lost when a piece moves from or to each square (king and castling rook homes)
+    pub castling_mask: [u8; 64],
     pub ep_square: u8,
     pub hash: u64,
+    /// plies since the last capture or pawn move (fifty-move rule)
+    pub halfmove_clock: u16,
+    /// starts at 1, incremented after every black move
+    pub fullmove_number: u16,
 
     // ---- derived caches (kept for now to reduce blast radius) ----
     pub pinned: u64,
@@ -47,9 +67,29 @@ pub struct Board {
     pub history: Vec<Undo>,
 
     pub ply: u16,
-    pub nnue_acc_w: [i32; 256],
-    pub nnue_acc_b: [i32; 256],
-    pub nnue_inited: bool,
+}
+
+/// Two boards are equal when they describe the same position: pieces, side to move,
+/// castling rights (and their rooks) and en passant square. Move counters, history and
+/// cached data are ignored, so boards reached by different move orders compare equal.
+impl PartialEq for Board {
+    fn eq(&self, other: &Self) -> bool {
+        self.pieces == other.pieces
+            && self.turn == other.turn
+            && self.castling_rights == other.castling_rights
+            // a lost right's rook square is stale, only the live ones matter
+            && (0..4).all(|i| self.castling_rights & (1 << i) == 0 || self.castling_rooks[i] == other.castling_rooks[i])
+            && self.ep_square == other.ep_square
+    }
+}
+
+impl Eq for Board {}
+
+/// Hashes the zobrist key, which covers exactly what `eq` compares.
+impl std::hash::Hash for Board {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
 }
 
 impl Board {
@@ -62,8 +102,12 @@ impl Board {
             piecelocs: PieceLocations::new(),
 
             castling_rights: 0,
+            castling_rooks: [64; 4],
+            castling_mask: [0; 64],
             ep_square: 64,
             hash: 0,
+            halfmove_clock: 0,
+            fullmove_number: 1,
 
             pinned: 0,
             pinners: 0,
@@ -72,13 +116,12 @@ impl Board {
             history: Vec::new(),
 
             ply: 0,
-            nnue_acc_b: [0; 256],
-            nnue_acc_w: [0; 256],
-            nnue_inited: false,
         }
     }
 
 
+    /// Copy of the position without its move history: the copy cannot `pop` or see
+    /// repetitions of earlier positions. Use `clone()` unless that is what you want.
     pub fn clone_position(&self) -> Board {
         Board {
             occupied: self.occupied,
@@ -88,8 +131,12 @@ impl Board {
             piecelocs: self.piecelocs,
 
             castling_rights: self.castling_rights,
+            castling_rooks: self.castling_rooks,
+            castling_mask: self.castling_mask,
             ep_square: self.ep_square,
             hash: self.hash,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
 
             pinned: self.pinned,
             pinners: self.pinners,
@@ -97,17 +144,60 @@ impl Board {
 
             history: Vec::new(),   // key point: new empty stack
             ply: self.ply,
-            nnue_acc_w: self.nnue_acc_w,
-            nnue_acc_b: self.nnue_acc_b,
-            nnue_inited: self.nnue_inited,
         }
     }
+
+    /// The same position with colors swapped and ranks mirrored: white's pieces become
+    /// black's on the opposite side of the board, the other side moves, and castling
+    /// rights and the en passant square follow. Any sound evaluation scores both alike.
+    /// Like `clone_position`, the result has no history.
+    pub fn flipped(&self) -> Board {
+        let mut b = Board::new();
+        for i in 0..12 {
+            b.pieces[(i + 6) % 12] = self.pieces[i].swap_bytes();
+        }
+        for c in 0..2 {
+            b.playerpieces[c ^ 1] = self.playerpieces[c].swap_bytes();
+            // attacked[] is indexed by the side under attack, which swaps too
+            b.attacked[c ^ 1] = self.attacked[c].swap_bytes();
+        }
+        b.occupied = self.occupied.swap_bytes();
+        for sq in 0..64u8 {
+            let p = self.piecelocs.piece_at(sq);
+            if p != Piece::None {
+                b.piecelocs.place(constlib::mirror_sq(sq as usize) as u8, Piece::make(p.get_color() ^ 1, p.get_piece_type()));
+            }
+        }
+        b.turn = self.turn ^ 1;
+
+        // rights are K, Q, k, q: swapping colors swaps the two pairs
+        let swap_rights = |r: u8| ((r & 0b0011) << 2) | ((r >> 2) & 0b0011);
+        b.castling_rights = swap_rights(self.castling_rights);
+        for idx in 0..4 {
+            let rsq = self.castling_rooks[idx];
+            b.castling_rooks[idx ^ 2] = if rsq == 64 { 64 } else { rsq ^ 56 };
+        }
+        for sq in 0..64 {
+            b.castling_mask[constlib::mirror_sq(sq)] = swap_rights(self.castling_mask[sq]);
+        }
+        b.ep_square = if self.ep_square == 64 { 64 } else { self.ep_square ^ 56 };
+        b.halfmove_clock = self.halfmove_clock;
+        b.fullmove_number = self.fullmove_number;
+
+        // the side to move keeps its pins, just mirrored
+        b.pinned = self.pinned.swap_bytes();
+        b.pinners = self.pinners.swap_bytes();
+        b.ply = self.ply;
+        b.hash = Self::compute_hash(&b);
+        b
+    }
     #[inline(always)]
     fn file_of(sq: u8) -> usize {
         (sq & 7) as usize
     }
 
-    pub fn push(&mut self, bm: Move, movegen: &movegen::MoveGenerator, nnue: &Nnue) {
+    /// Plays a legal move. NNUE accumulators live outside the board (see `AccumulatorStack`).
+    pub fn push(&mut self, bm: Move, movegen: &movegen::MoveGenerator) {
         let push_timer = Instant::now();
         let color = self.turn;
         let enemy = color ^ 1;
@@ -124,10 +214,7 @@ impl Board {
         assert!(piece != Piece::None);
 
         // ---- save undo (previous true state) ----
-        if !self.nnue_inited {
-            self.nnue_rebuild(nnue);
-        }
-        let mut undo = Undo::new(bm, self.castling_rights, self.ep_square, self.hash, self.nnue_acc_w, self.nnue_acc_b);
+        let mut undo = Undo::new(bm, self.castling_rights, self.ep_square, self.hash, self.halfmove_clock);
 
         // ---- incremental zobrist: start from previous hash and remove old EP/castling ----
         let old_castle = self.castling_rights;
@@ -136,7 +223,7 @@ impl Board {
         if old_ep != 64 {
             h ^= Z_EP_FILE[Self::file_of(old_ep)];
         }
-        h ^= Z_CASTLING[(old_castle & 0x0F) as usize];
+        h ^= self.castling_key(old_castle);
 
         self.ply += 1;
 
@@ -145,10 +232,8 @@ impl Board {
             self.apply_castling(from as i8, to as i8);
 
             // hash update for castling (king + rook squares)
-            let ksrc = from as i8;
-            let rsrc = to as i8;
-            let kingside = ksrc < rsrc;
-            let (kdst, rdst) = if kingside { (ksrc + 2, ksrc + 1) } else { (ksrc - 2, ksrc - 1) };
+            let (ksrc, rsrc) = (from, to);
+            let (kdst, rdst) = castling::castle_destinations(ksrc, rsrc);
 
             let king_idx = (6 * color as usize) + PieceIndex::K.index();
             let rook_idx = (6 * color as usize) + PieceIndex::R.index();
@@ -245,15 +330,25 @@ impl Board {
         }
 
         // ---- castling rights update (based on from/to squares) ----
-        let updatecastlemask = !(castling::get_rights(to) | castling::get_rights(from));
+        let updatecastlemask = !(self.castling_mask[to as usize] | self.castling_mask[from as usize]);
         self.castling_rights &= updatecastlemask;
 
+        // ---- move counters ----
+        if capture || piece.get_piece_type() == PieceType::P {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if color == 1 {
+            self.fullmove_number += 1;
+        }
+
         // ---- side to move ----
         self.turn = enemy;
         h ^= Z_SIDE;
 
         // ---- incremental zobrist: add new castling/EP ----
-        h ^= Z_CASTLING[(self.castling_rights & 0x0F) as usize];
+        h ^= self.castling_key(self.castling_rights);
         if self.ep_square != 64 {
             h ^= Z_EP_FILE[Self::file_of(self.ep_square)];
         }
@@ -266,86 +361,6 @@ impl Board {
         // self.attacked[self.turn as usize] = movegen.makeattackedmask(self, self.occupied);
 
 
-        // ---- NNUE INCREMENTAL ACC update ----
-        let nnue_timer = Instant::now();
-        // Assumes self.nnue_acc_w / self.nnue_acc_b currently represent the PRE-move position
-        // (we ensured initialization + created Undo snapshot before making changes).
-
-        let mover_is_king = piece.get_piece_type() == PieceType::K;
-
-        if castle || mover_is_king {
-            // HalfKP depends on king square: easiest correct rule is rebuild on king moves / castling.
-            self.nnue_rebuild(nnue);
-        } else {
-            let wk_sq = self.pieces[PieceIndex::K.index()].trailing_zeros() as usize;
-            let bk_sq = self.pieces[6 + PieceIndex::K.index()].trailing_zeros() as usize;
-
-            let mover_idx = piece.getidx();
-
-            if prom {
-                // Promotion: pawn(from) removed, promoted(to) added, plus capture removed if any.
-                nnue_sub_piece(
-                    nnue,
-                    &mut self.nnue_acc_w,
-                    &mut self.nnue_acc_b,
-                    wk_sq,
-                    bk_sq,
-                    mover_idx,
-                    from as usize,
-                );
-
-                if undo.captured_piece != Piece::None {
-                    nnue_sub_piece(
-                        nnue,
-                        &mut self.nnue_acc_w,
-                        &mut self.nnue_acc_b,
-                        wk_sq,
-                        bk_sq,
-                        undo.captured_piece.getidx(),
-                        undo.captured_sq as usize,
-                    );
-                }
-
-                let prompiece = bm.prompiece().to_piece(color);
-                nnue_add_piece(
-                    nnue,
-                    &mut self.nnue_acc_w,
-                    &mut self.nnue_acc_b,
-                    wk_sq,
-                    bk_sq,
-                    prompiece.getidx(),
-                    to as usize,
-                );
-            } else {
-                // Normal move: mover from->to
-                nnue_move_piece(
-                    nnue,
-                    &mut self.nnue_acc_w,
-                    &mut self.nnue_acc_b,
-                    wk_sq,
-                    bk_sq,
-                    mover_idx,
-                    from as usize,
-                    to as usize
-                );
-
-
-                // Capture (including EP): remove captured at captured_sq
-                if undo.captured_piece != Piece::None {
-                    nnue_sub_piece(
-                        nnue,
-                        &mut self.nnue_acc_w,
-                        &mut self.nnue_acc_b,
-                        wk_sq,
-                        bk_sq,
-                        undo.captured_piece.getidx(),
-                        undo.captured_sq as usize,
-                    );
-                }
-            }
-        }
-        perf::record_push_nn_update(nnue_timer.elapsed());
-
         #[cfg(debug_assertions)]
         {
             let recomputed = Self::compute_hash(self); // rebuild from pieces + side + rights + ep
@@ -387,7 +402,7 @@ self.debug_validate();
 
     }
 
-    pub fn pop(&mut self, movegen: &movegen::MoveGenerator, nnue: &Nnue) {
+    pub fn pop(&mut self) {
         let pop_timer = Instant::now();
         let undo = match self.history.pop() {
             Some(u) => u,
@@ -480,15 +495,15 @@ self.debug_validate();
         self.castling_rights = undo.castling_rights;
         self.ep_square = undo.ep_square;
         self.hash = undo.hash;
+        self.halfmove_clock = undo.halfmove_clock;
+        if color == 1 {
+            self.fullmove_number -= 1;
+        }
 
         // Restore side-to-move and ply
         self.turn = color;
         self.ply -= 1;
 
-
-        self.nnue_acc_w = undo.nnue_acc_w;
-        self.nnue_acc_b = undo.nnue_acc_b;
-        self.nnue_inited = true;
         // Refresh derived caches
         // let pin = movegen.getpinned(self);
         // self.pinned = pin.0;
@@ -505,55 +520,148 @@ self.debug_validate();
 
     }
 
-    pub fn apply_castling(&mut self, ksrc: i8, rsrc: i8) {
-        let kingside = ksrc < rsrc;
-        let us = self.turn;
-        let (kdst, rdst) = if kingside { (ksrc + 2, ksrc + 1) } else { (ksrc - 2, ksrc - 1) };
+    /// Passes the turn without moving a piece (null move), for null-move pruning and
+    /// threat detection. Only legal when the side to move is not in check.
+    pub fn push_null(&mut self) {
+        let undo = Undo::new(Move::new(), self.castling_rights, self.ep_square, self.hash, self.halfmove_clock);
+        self.history.push(undo);
 
-        let kingidx = 6 * us as usize + PieceIndex::K.index();
-        let rookidx = 6 * us as usize + PieceIndex::R.index();
+        if self.ep_square != 64 {
+            self.hash ^= Z_EP_FILE[Self::file_of(self.ep_square)];
+            self.ep_square = 64;
+        }
+        self.hash ^= Z_SIDE;
+        self.halfmove_clock += 1;
+        if self.turn == 1 {
+            self.fullmove_number += 1;
+        }
+        self.turn ^= 1;
+        self.ply += 1;
 
-        self.pieces[kingidx] ^= (1u64 << ksrc) | (1u64 << kdst);
-        self.pieces[rookidx] ^= (1u64 << rsrc) | (1u64 << rdst);
+        debug_assert_eq!(self.hash, Self::compute_hash(self));
+    }
 
-        self.occupied ^= (1u64 << ksrc) | (1u64 << kdst) | (1u64 << rsrc) | (1u64 << rdst);
+    /// Takes back a `push_null`.
+    pub fn pop_null(&mut self) {
+        let undo = self.history.pop().expect("Board::pop_null called with empty history");
+        debug_assert!(undo.mv.is_null(), "pop_null called on a real move");
 
-        let king = self.piecelocs.piece_at(ksrc as u8);
-        let rook = self.piecelocs.piece_at(rsrc as u8);
+        self.turn ^= 1;
+        if self.turn == 1 {
+            self.fullmove_number -= 1;
+        }
+        self.ep_square = undo.ep_square;
+        self.hash = undo.hash;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.ply -= 1;
+    }
 
-        self.piecelocs.remove(ksrc as u8);
-        self.piecelocs.place(kdst as u8, king);
+    pub fn apply_castling(&mut self, ksrc: i8, rsrc: i8) {
+        let us = self.turn;
+        self.move_castling_pieces(us, ksrc as u8, rsrc as u8, true);
+    }
 
-        self.piecelocs.remove(rsrc as u8);
-        self.piecelocs.place(rdst as u8, rook);
+    pub fn undo_castling(&mut self, ksrc: i8, rsrc: i8) {
+        // pop calls this before restoring the side to move
+        let us = self.turn ^ 1;
+        self.move_castling_pieces(us, ksrc as u8, rsrc as u8, false);
+    }
 
-        self.playerpieces[us as usize] ^= (1u64 << ksrc) | (1u64 << kdst) | (1u64 << rsrc) | (1u64 << rdst);
+    // In Chess960 the king or rook may stay put or land on the other's start square,
+    // so both pieces are lifted off before either is put down.
+    fn move_castling_pieces(&mut self, color: u8, ksrc: u8, rsrc: u8, forward: bool) {
+        let (kdst, rdst) = castling::castle_destinations(ksrc, rsrc);
+        let (kfrom, kto, rfrom, rto) = if forward { (ksrc, kdst, rsrc, rdst) } else { (kdst, ksrc, rdst, rsrc) };
+
+        let kingidx = 6 * color as usize + PieceIndex::K.index();
+        let rookidx = 6 * color as usize + PieceIndex::R.index();
+        let king = self.piecelocs.piece_at(kfrom);
+        let rook = self.piecelocs.piece_at(rfrom);
+
+        let lifted = (1u64 << kfrom) | (1u64 << rfrom);
+        let placed = (1u64 << kto) | (1u64 << rto);
+
+        self.pieces[kingidx] = (self.pieces[kingidx] & !(1u64 << kfrom)) | (1u64 << kto);
+        self.pieces[rookidx] = (self.pieces[rookidx] & !(1u64 << rfrom)) | (1u64 << rto);
+        self.occupied = (self.occupied & !lifted) | placed;
+        self.playerpieces[color as usize] = (self.playerpieces[color as usize] & !lifted) | placed;
+
+        self.piecelocs.remove(kfrom);
+        self.piecelocs.remove(rfrom);
+        self.piecelocs.place(kto, king);
+        self.piecelocs.place(rto, rook);
     }
 
-    pub fn undo_castling(&mut self, ksrc: i8, rsrc: i8) {
-        let kingside = ksrc < rsrc;
-        let us = self.turn;
-        let enemy = us ^ 1;
-        let (kdst, rdst) = if kingside { (ksrc + 2, ksrc + 1) } else { (ksrc - 2, ksrc - 1) };
+    /// Zobrist key of a castling-rights value. Rights whose rook is not on its
+    /// standard corner also hash the rook square, so two Chess960 positions that
+    /// differ only in which rook may castle get different keys.
+    fn castling_key(&self, rights: u8) -> u64 {
+        const CORNERS: [u8; 4] = [7, 0, 63, 56];
+        let mut h = Z_CASTLING[(rights & 0x0F) as usize];
+        for i in 0..4 {
+            let rook = self.castling_rooks[i];
+            if rights & (1 << i) != 0 && rook != CORNERS[i] {
+                h ^= Z_CASTLING_ROOK[rook as usize];
+            }
+        }
+        h
+    }
 
-        let kingidx = 6 * enemy as usize + PieceIndex::K.index();
-        let rookidx = 6 * enemy as usize + PieceIndex::R.index();
+    /// True if the current position already occurred earlier in the game or search path
+    /// (twofold; search treats this as a draw). Only positions since the last irreversible
+    /// move can match, so the scan is bounded by the halfmove clock.
+    pub fn is_repetition(&self) -> bool {
+        self.repetitions(1)
+    }
 
-        self.pieces[kingidx] ^= (1u64 << ksrc) | (1u64 << kdst);
-        self.pieces[rookidx] ^= (1u64 << rsrc) | (1u64 << rdst);
+    /// True if the current position has now occurred three times.
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.repetitions(2)
+    }
 
-        self.occupied ^= (1u64 << ksrc) | (1u64 << kdst) | (1u64 << rsrc) | (1u64 << rdst);
+    fn repetitions(&self, needed: usize) -> bool {
+        let n = self.history.len();
+        let limit = (self.halfmove_clock as usize).min(n);
+        let mut found = 0;
+        for i in 1..=limit {
+            let undo = &self.history[n - i];
+            // a passed turn (null move) is not a real game move, so nothing before it repeats
+            if undo.mv.is_null() {
+                break;
+            }
+            // same side to move => even distance; the earliest possible repeat is 4 plies back
+            if i >= 4 && i % 2 == 0 && undo.hash == self.hash {
+                found += 1;
+                if found >= needed {
+                    return true;
+                }
+            }
+        }
+        false
+    }
 
-        let king = self.piecelocs.piece_at(kdst as u8);
-        let rook = self.piecelocs.piece_at(rdst as u8);
+    pub fn is_fifty_move_draw(&self) -> bool {
+        self.halfmove_clock >= 100
+    }
 
-        self.piecelocs.remove(kdst as u8);
-        self.piecelocs.place(ksrc as u8, king);
+    /// Dead positions: K v K, K+minor v K, and bishops-only endings where every
+    /// bishop stands on the same square color.
+    pub fn is_insufficient_material(&self) -> bool {
+        let heavy_or_pawns = self.pieces[PieceIndex::P.index()] | self.pieces[PieceIndex::p.index()]
+            | self.pieces[PieceIndex::R.index()] | self.pieces[PieceIndex::r.index()]
+            | self.pieces[PieceIndex::Q.index()] | self.pieces[PieceIndex::q.index()];
+        if heavy_or_pawns != 0 {
+            return false;
+        }
 
-        self.piecelocs.remove(rdst as u8);
-        self.piecelocs.place(rsrc as u8, rook);
+        let knights = self.pieces[PieceIndex::N.index()] | self.pieces[PieceIndex::n.index()];
+        let bishops = self.pieces[PieceIndex::B.index()] | self.pieces[PieceIndex::b.index()];
+        if (knights | bishops).count_ones() <= 1 {
+            return true;
+        }
 
-        self.playerpieces[enemy as usize] ^= (1u64 << ksrc) | (1u64 << kdst) | (1u64 << rsrc) | (1u64 << rdst);
+        const DARK_SQUARES: u64 = 0xAA55_AA55_AA55_AA55;
+        knights == 0 && (bishops & DARK_SQUARES == 0 || bishops & !DARK_SQUARES == 0)
     }
 
     pub fn piece_exists_at(&self, rank: usize, file: usize) -> bool {
@@ -565,51 +673,51 @@ self.debug_validate();
     pub fn getattacked(&self) -> [u64; 2] { self.attacked }
     pub fn getep(&self) -> u8 { self.ep_square }
 
-    // creates board from fen string
-    pub fn from_fen(&mut self, fen: String, nnue: &Nnue) {
-        let mut fields = fen.split(" ");
-        let pieces = fields.next().unwrap().chars();
-        let mut rank: usize = 7;
-        let mut file: usize = 0;
-
-        // reset
-        *self = Board::new();
-
-        for c in pieces {
-            if c.is_numeric() {
-                file += c.to_digit(10).unwrap() as usize;
-            } else if c.is_alphabetic() {
-                self.put_piece(c, rank, file);
-                file += 1;
-            } else if c == '/' {
-                rank -= 1;
-                file = 0;
+    // creates board from fen string; panics on malformed input, see `try_from_fen`
+    pub fn from_fen(&mut self, fen: String) {
+        *self = Board::try_from_fen(&fen).unwrap_or_else(|e| panic!("invalid FEN '{}': {}", fen, e));
+    }
+
+    /// Serializes the position to FEN; round-trips exactly with `from_fen`.
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+        for rank in (0..8u8).rev() {
+            let mut empty = 0;
+            for file in 0..8u8 {
+                let p = self.piecelocs.piece_at(rank * 8 + file);
+                if p == Piece::None {
+                    empty += 1;
+                    continue;
+                }
+                if empty > 0 {
+                    fen.push_str(&empty.to_string());
+                    empty = 0;
+                }
+                let ch = p.get_piece_type().get_piece_type();
+                fen.push(if p.get_color() == 0 { ch.to_ascii_uppercase() } else { ch });
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                fen.push('/');
             }
         }
 
-        let color = fields.next().unwrap();
-        let castling_rights = fields.next().unwrap();
-        let ep = fields.next().unwrap();
-
-        self.ep_square = if ep == "-" { 64 } else { constlib::square_from_string(ep) };
-        self.turn = if color.chars().next().unwrap() == 'w' { 0 } else { 1 };
-        self.castling_rights = castling::get_castling_mask(castling_rights);
-
-        self.nnue_inited = false;
-        self.nnue_rebuild(nnue);
-
-        // initialize caches and hash
-        let mg = movegen::MoveGenerator::new();
-        let pininfo = mg.getpinned(self);
-        self.pinned = pininfo.0;
-        self.pinners = pininfo.1;
-        self.attacked[self.turn as usize] = mg.makeattackedmask(self, self.turn, self.occupied);
-        self.hash = Self::compute_hash(self);
-
+        fen.push_str(if self.turn == 0 { " w " } else { " b " });
+        fen.push_str(&self.castling_field());
+        fen.push(' ');
+        if self.ep_square == 64 {
+            fen.push('-');
+        } else {
+            fen.push_str(&constlib::squaretouci(self.ep_square));
+        }
+        fen.push_str(&format!(" {} {}", self.halfmove_clock, self.fullmove_number));
+        fen
     }
 
     pub fn compute_hash(board: &crate::core::Board) -> u64 {
-        use crate::core::zobrist::{Z_PIECE_SQ, Z_SIDE, Z_CASTLING, Z_EP_FILE};
+        use crate::core::zobrist::{Z_PIECE_SQ, Z_SIDE, Z_EP_FILE};
         use crate::core::constlib;
 
         let mut h: u64 = 0;
@@ -623,7 +731,7 @@ self.debug_validate();
         }
 
         if board.turn == 1 { h ^= Z_SIDE; }
-        h ^= Z_CASTLING[(board.castling_rights & 0x0F) as usize];
+        h ^= board.castling_key(board.castling_rights);
 
         if board.ep_square != 64 {
             h ^= Z_EP_FILE[Self::file_of(board.ep_square)];
@@ -631,8 +739,8 @@ self.debug_validate();
         h
     }
 
-    pub fn set_startpos(&mut self, nnue: &Nnue) {
-        self.from_fen(String::from("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), nnue);
+    pub fn set_startpos(&mut self) {
+        self.from_fen(String::from("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
     }
 
     pub fn put_piece(&mut self, ch: char, rank: usize, file: usize) {
@@ -755,33 +863,5 @@ pub fn debug_validate(&self) {
         }
     }
 }
-    pub fn nnue_rebuild(&mut self, nnue: &crate::evaluate::nnue::Nnue) {
-        debug_assert_eq!(nnue.b1.len(), 256);
-
-        // Start from bias
-        self.nnue_acc_w.copy_from_slice(&nnue.b1);
-        self.nnue_acc_b.copy_from_slice(&nnue.b1);
-
-        let wk_sq = self.pieces[PieceIndex::K.index()].trailing_zeros() as usize;
-        let bk_sq = self.pieces[6 + PieceIndex::K.index()].trailing_zeros() as usize;
-
-        for piece_idx in 0..12usize {
-            let mut bb = self.pieces[piece_idx];
-            while bb != 0 {
-                let sq = constlib::poplsb(&mut bb) as usize;
-                nnue_add_piece(
-                    nnue,
-                    &mut self.nnue_acc_w,
-                    &mut self.nnue_acc_b,
-                    wk_sq,
-                    bk_sq,
-                    piece_idx,
-                    sq,
-                );
-            }
-        }
-
-        self.nnue_inited = true;
-    }
 
 }
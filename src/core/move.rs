@@ -5,6 +5,7 @@
 //! the next two bits are a special move flag: promotion (1), en passant (2), castling (3)
 use crate::core::piece::PieceType;
 use crate::core::constlib;
+use crate::core::movelist::MoveList;
 
 //CODES:
 // 0000  ===> Quiet move
@@ -67,15 +68,36 @@ impl Move {
       let dst = constlib::squaretouci(self.getDst());
       format!("{}{}", src, dst)
     }
-    pub fn movemasktoBitMoves(src: u8, movemask: &mut u64)-> Vec<Move>
+    /// UCI long algebraic notation ("e2e4", "e7e8q").
+    /// Castling is internally king-takes-rook; UCI wants the king's destination.
+    pub fn to_uci(self) -> String {
+      self.to_uci_mode(false)
+    }
+    /// `to_uci`, except that with `chess960` castling stays king-takes-rook ("e1h1"),
+    /// which is what UCI_Chess960 asks for.
+    pub fn to_uci_mode(self, chess960: bool) -> String {
+      if self.is_null() {
+        return String::from("0000");
+      }
+      let src = self.getSrc();
+      let mut dst = self.getDst();
+      if self.iscastle() && !chess960 {
+        let rank = src & !7;
+        dst = if dst > src { rank + 6 } else { rank + 2 };
+      }
+      let mut s = format!("{}{}", constlib::squaretouci(src), constlib::squaretouci(dst));
+      if self.isprom() {
+        s.push(self.prompiece().get_piece_type());
+      }
+      s
+    }
+    pub fn movemasktoBitMoves(src: u8, movemask: &mut u64, movelist: &mut MoveList)
       {
-        let mut vec = Vec::new();
         while *movemask != 0 {
           let dst = constlib::poplsb(movemask) as u8;
           let bitm = Move::make(src,dst, 0);
-          vec.push(bitm);
+          movelist.push(bitm);
         }
-        return vec;
       }
     pub fn is_null(self) -> bool {
       self.data == 0
@@ -1,7 +1,9 @@
 use std::f32::consts::E;
+use std::sync::OnceLock;
 
 use super::*;
 use crate::core::constlib;
+use crate::core::magic;
 use crate::core::r#move::*;
 use crate::core::castling::*;
 use crate::core::piece::PieceIndex;
@@ -14,7 +16,9 @@ pub struct MoveGenerator {
     pub pawnmoves: [[u64; 64];2], 
     pub bishop: [u64; 64],
     pub rook: [u64; 64],
-    pub line_between:[[u64;64];64],
+    pub line_between:[[u64;64];64], //whole line through two aligned squares, edge to edge
+    pub between:[[u64;64];64], //squares strictly between two aligned squares
+    pub ray:[[u64;64];64], //from the first square through the second, out to the edge
 }
 
 impl MoveGenerator {
@@ -28,18 +32,28 @@ impl MoveGenerator {
             bishop: [0; 64],
             rook: [0; 64],
             line_between:[[0;64];64],
+            between:[[0;64];64],
+            ray:[[0;64];64],
         };
         moveg.init_king();
         moveg.init_knight();
         moveg.init_pawnattacks();
         moveg.init_pawnmoves();
+        moveg.init_sliders();
         moveg.gen_line_bbs();
         moveg
     }
+
+    /// One generator for the whole process, for code that has none at hand (FEN parsing).
+    /// Built on first use.
+    pub fn shared() -> &'static MoveGenerator {
+        static SHARED: OnceLock<MoveGenerator> = OnceLock::new();
+        SHARED.get_or_init(MoveGenerator::new)
+    }
     #[inline(always)]
-    pub fn generate(& self, board: &mut Board)->Vec<Move>
+    pub fn generate(& self, board: &mut Board)->MoveList
       {
-        let mut moves = Vec::new();
+        let mut moves = MoveList::new();
         
         let us = board.turn;
         let them = us ^ 1;
@@ -87,7 +101,7 @@ impl MoveGenerator {
         moves
       }
     #[inline(always)]
-    pub fn generatekingmoves(&self,board:&Board,movelist:&mut Vec<Move>, kingsq:i8, enemy_attacks: u64) {
+    pub fn generatekingmoves(&self,board:&Board,movelist:&mut MoveList, kingsq:i8, enemy_attacks: u64) {
       let color = board.turn;
       let them = if color == 0 {1} else {0};
       
@@ -111,7 +125,7 @@ impl MoveGenerator {
       self.generatecastling(board, movelist, enemy_attacks);
     }
     #[inline(always)]
-    pub fn generatequeenmoves(&self, board:&Board, movelist:&mut Vec<Move>,evasions:bool,target:(u64,u64),pininfo:(u64,u64), kingsq:i8) {
+    pub fn generatequeenmoves(&self, board:&Board, movelist:&mut MoveList,evasions:bool,target:(u64,u64),pininfo:(u64,u64), kingsq:i8) {
       let color = board.turn;
       let mut qbb = if (color == 0) {board.pieces[PieceIndex::Q.index()]} else {board.pieces[PieceIndex::q.index()]};
       let them = if color == 0 {1} else {0};
@@ -140,7 +154,7 @@ impl MoveGenerator {
       }
     }
     #[inline(always)]
-    pub fn generaterookmoves(&self, board:&Board, movelist:&mut Vec<Move>,evasions:bool,target:(u64,u64),pininfo:(u64,u64), kingsq:i8) {
+    pub fn generaterookmoves(&self, board:&Board, movelist:&mut MoveList,evasions:bool,target:(u64,u64),pininfo:(u64,u64), kingsq:i8) {
       let color = board.turn;
       let mut rbb = if (color == 0) {board.pieces[PieceIndex::R.index()]} else {board.pieces[PieceIndex::r.index()]};
       let them = if color == 0 {1} else {0};
@@ -169,7 +183,7 @@ impl MoveGenerator {
       }
     }
     #[inline(always)]
-    pub fn generatebishopmoves(&self, board:&Board, movelist:&mut Vec<Move>,evasions:bool,target:(u64,u64),pininfo:(u64,u64), kingsq:i8) {
+    pub fn generatebishopmoves(&self, board:&Board, movelist:&mut MoveList,evasions:bool,target:(u64,u64),pininfo:(u64,u64), kingsq:i8) {
       let color = board.turn;
       let mut bbb = if (color == 0) {board.pieces[PieceIndex::B.index()]} else {board.pieces[PieceIndex::b.index()]};
       let them = if color == 0 {1} else {0};
@@ -197,7 +211,7 @@ impl MoveGenerator {
       }
     }
     #[inline(always)]
-    pub fn generateknightmoves(&self,board: &Board, movelist: &mut Vec<Move>,evasions:bool,target:(u64,u64),pininfo:(u64,u64)) {
+    pub fn generateknightmoves(&self,board: &Board, movelist: &mut MoveList,evasions:bool,target:(u64,u64),pininfo:(u64,u64)) {
       let color = board.turn;
       let enemy = if color == 0 {1} else {0};
       let mut kbb = if color == 0 {board.pieces[PieceIndex::N.index()]} else {board.pieces[PieceIndex::n.index()]};
@@ -225,7 +239,7 @@ impl MoveGenerator {
       
     }
     #[inline(always)]
-    pub fn generatepawnmoves(&self,board: &Board, movelist: &mut Vec<Move>,evasions:bool,target:(u64,u64),pininfo:(u64,u64),kingsq:i8) {
+    pub fn generatepawnmoves(&self,board: &Board, movelist: &mut MoveList,evasions:bool,target:(u64,u64),pininfo:(u64,u64),kingsq:i8) {
       //looks up pawn attacks and moves, finds out if they're possible
       //creates a bitmove for each possible move
       //mutates given array
@@ -309,6 +323,20 @@ impl MoveGenerator {
           attacks &= target.0 | target.1;
         }
         
+        //create capture bitmoves (before EP, which may skip to the next pawn)
+        while attacks != 0 
+        {
+          let dst = constlib::poplsb(&mut attacks) as u8;
+          if(dst / 8 == 7 || dst / 8  == 0)
+          {
+            movelist.push(Move::makePromCap(ind as u8, dst, PieceType::B));
+            movelist.push(Move::makePromCap(ind as u8, dst, PieceType::N));
+            movelist.push(Move::makePromCap(ind as u8, dst, PieceType::R));
+            movelist.push(Move::makePromCap(ind as u8, dst, PieceType::Q));
+          } else {movelist.push(Move::makeCapture(ind as u8, dst))
+          }
+        }
+
        //generate ep moves
         let ep = board.getep();
 
@@ -350,23 +378,25 @@ impl MoveGenerator {
           }
           
         }
-        while attacks != 0 
-        {
-          let dst = constlib::poplsb(&mut attacks) as u8;
-          if(dst / 8 == 7 || dst / 8  == 0)
-          {
-            movelist.push(Move::makePromCap(ind as u8, dst, PieceType::B));
-            movelist.push(Move::makePromCap(ind as u8, dst, PieceType::N));
-            movelist.push(Move::makePromCap(ind as u8, dst, PieceType::R));
-            movelist.push(Move::makePromCap(ind as u8, dst, PieceType::Q));
-          } else {movelist.push(Move::makeCapture(ind as u8, dst))
-          }
-        }
-        //create capture bitmoves
-        //save legalmoves for this square
         }
       }
 
+    pub fn init_sliders(&mut self) {
+      magic::init();
+      for i in 0..64 {
+        //empty board attacks
+        self.bishop[i] = magic::bishop_attacks(i as u8, 0);
+        self.rook[i] = magic::rook_attacks(i as u8, 0);
+      }
+    }
+    #[inline(always)]
+    pub fn bishop_attacks(&self, sq: u8, occ: u64) -> u64 {
+      magic::bishop_attacks(sq, occ)
+    }
+    #[inline(always)]
+    pub fn rook_attacks(&self, sq: u8, occ: u64) -> u64 {
+      magic::rook_attacks(sq, occ)
+    }
     pub fn init_pawnmoves(&mut self) {
       for i in 8..56 {
         let mut wmoves = 0;
@@ -449,7 +479,7 @@ impl MoveGenerator {
     
     
     #[inline(always)]
-    pub fn generatecastling(&self, board: &Board, movelist: &mut Vec<Move>, enemy_attacks: u64) {
+    pub fn generatecastling(&self, board: &Board, movelist: &mut MoveList, enemy_attacks: u64) {
     let color = board.turn;
     let rights = board.castling_rights;
     if oppressed(rights) { return; }
@@ -457,71 +487,34 @@ impl MoveGenerator {
     // Squares attacked by the opponent (reliable for legality)
     let kingdanger = enemy_attacks;
 
-    if color == 0 {
-        let kingsq: u8 = constlib::square_from_string("e1");
-
-        // White king-side: empty f1,g1; and e1,f1,g1 not attacked
-        if wkingside(rights) {
-            let empty_between =
-                constlib::squarebb_from_string("f1") |
-                constlib::squarebb_from_string("g1");
-            let king_path =
-                constlib::squarebb_from_string("e1") |
-                constlib::squarebb_from_string("f1") |
-                constlib::squarebb_from_string("g1");
-
-            if (board.occupied & empty_between) == 0 && (kingdanger & king_path) == 0 {
-                movelist.push(Move::makeKingCastle(kingsq, 7));
-            }
-        }
+    let king_bb = board.pieces[6 * color as usize + PieceIndex::K.index()];
+    let kingsq = king_bb.trailing_zeros() as u8;
 
-        // White queen-side: empty b1,c1,d1; and e1,d1,c1 not attacked
-        if wqueenside(rights) {
-            let empty_between =
-                constlib::squarebb_from_string("b1") |
-                constlib::squarebb_from_string("c1") |
-                constlib::squarebb_from_string("d1");
-            let king_path =
-                constlib::squarebb_from_string("e1") |
-                constlib::squarebb_from_string("d1") |
-                constlib::squarebb_from_string("c1");
-
-            if (board.occupied & empty_between) == 0 && (kingdanger & king_path) == 0 {
-                movelist.push(Move::makeQueenCastle(kingsq, 0));
-            }
+    for kingside in [true, false] {
+        let idx = right_index(color, kingside);
+        if rights & (1 << idx) == 0 {
+            continue;
         }
-    } else {
-        let kingsq: u8 = constlib::square_from_string("e8");
-
-        // Black king-side: empty f8,g8; and e8,f8,g8 not attacked
-        if bkingside(rights) {
-            let empty_between =
-                constlib::squarebb_from_string("f8") |
-                constlib::squarebb_from_string("g8");
-            let king_path =
-                constlib::squarebb_from_string("e8") |
-                constlib::squarebb_from_string("f8") |
-                constlib::squarebb_from_string("g8");
-
-            if (board.occupied & empty_between) == 0 && (kingdanger & king_path) == 0 {
-                movelist.push(Move::makeKingCastle(kingsq, 63));
-            }
+        // works for any king/rook files (Chess960), the standard start is just one case
+        let rooksq = board.castling_rooks[idx];
+        let rook_bb = 1u64 << rooksq;
+        let (kdst, rdst) = castle_destinations(kingsq, rooksq);
+
+        // everything either piece crosses or lands on must be empty, apart from the two pieces themselves
+        let empty_between = (rank_span(kingsq, kdst) | rank_span(rooksq, rdst)) & !(king_bb | rook_bb);
+        let king_path = rank_span(kingsq, kdst);
+        if (board.occupied & empty_between) != 0 || (kingdanger & king_path) != 0 {
+            continue;
+        }
+        // the castling rook can be what shields the king's destination from a slider on the back rank
+        if self.is_square_attacked_by(board, board.occupied ^ rook_bb, color ^ 1, kdst) {
+            continue;
         }
 
-        // Black queen-side: empty b8,c8,d8; and e8,d8,c8 not attacked
-        if bqueenside(rights) {
-            let empty_between =
-                constlib::squarebb_from_string("b8") |
-                constlib::squarebb_from_string("c8") |
-                constlib::squarebb_from_string("d8");
-            let king_path =
-                constlib::squarebb_from_string("e8") |
-                constlib::squarebb_from_string("d8") |
-                constlib::squarebb_from_string("c8");
-
-            if (board.occupied & empty_between) == 0 && (kingdanger & king_path) == 0 {
-                movelist.push(Move::makeQueenCastle(kingsq, 56));
-            }
+        if kingside {
+            movelist.push(Move::makeKingCastle(kingsq, rooksq));
+        } else {
+            movelist.push(Move::makeQueenCastle(kingsq, rooksq));
         }
     }
 }
@@ -665,7 +658,7 @@ pub fn makeattackedmask_for_color(&self, board: &Board, color: u8, blockers: u64
         let ind = constlib::poplsb(&mut sliders);
 
         //lets check that the king is indeed on a diagonal from the sliding piece
-        if constlib::compute_bishop(kingsq, 0) & (1<<ind) ==0 {
+        if self.bishop[kingsq as usize] & (1<<ind) ==0 {
           //check if the king is on a diagonal from the given slider
           continue;
         }
@@ -700,7 +693,7 @@ pub fn makeattackedmask_for_color(&self, board: &Board, color: u8, blockers: u64
       while sliders != 0 {
         let ind = constlib::poplsb(&mut sliders);
         //lets check that the king is indeed on a orthogonal line from the sliding piece
-        if constlib::compute_rook(kingsq, 0) & (1<<ind) ==0 {
+        if self.rook[kingsq as usize] & (1<<ind) ==0 {
           //check if the king is on a diagonal from the given slider
           continue;
         }
@@ -795,11 +788,11 @@ pub fn makeattackedmask_for_color(&self, board: &Board, color: u8, blockers: u64
     pub fn enemy_attacks(&self, board: &Board) -> u64 {
         // Compute attacks by the side NOT to move (enemy of board.turn)
         // without mutating the board.
-        // This requires makeattackedmask-like logic that takes an explicit color.
-        self.makeattackedmask_for_color(board, board.turn ^ 1, board.occupied)
+        // makeattackedmask_for_color takes the *defending* color, so pass our own.
+        self.makeattackedmask_for_color(board, board.turn, board.occupied)
     }
     #[inline(always)]
-    pub fn genevasions(&self, board:&Board, movelist: &mut Vec<Move>, checkers: &mut u64, pininfo: (u64, u64), kingsq: i8, enemy_attacks: u64) {
+    pub fn genevasions(&self, board:&Board, movelist: &mut MoveList, checkers: &mut u64, pininfo: (u64, u64), kingsq: i8, enemy_attacks: u64) {
       
       let mut ct = 0;
       let mut checkerscopy = *checkers;
@@ -904,9 +897,9 @@ fn get_ray_mask_blockers(source_square: i8, target_square: i8, blockers: u64) ->
     ray_mask
 }
 #[inline(always)]
-pub fn generate_qcaptures(&self, board: &mut Board)-> Vec<Move>{
+pub fn generate_qcaptures(&self, board: &mut Board)-> MoveList{
 
-    let mut out = Vec::<Move>::new();
+    let mut out = MoveList::new();
     let us = board.turn;
     let them = us ^ 1;
 
@@ -986,12 +979,17 @@ pub fn generate_qcaptures(&self, board: &mut Board)-> Vec<Move>{
             }
         }
 
-        // En passant capture (tactical)
+        // En passant capture (tactical). Lifting both pawns off their rank can expose
+        // the king to a rook or queen, which the pin line above does not cover.
         if board.ep_square != 64 {
             let epsq = board.ep_square;
             let ep_mask = self.pawnattacks[us as usize][from as usize] & (1u64 << epsq) & pin_line;
             if ep_mask != 0 {
-                out.push(Move::makeEP(from, epsq));
+                let enemypawn = if us == 0 { epsq - 8 } else { epsq + 8 };
+                let blockers = (board.occupied & !((1u64 << from) | (1u64 << enemypawn))) | (1u64 << epsq);
+                if self.getcheckers(board, blockers) == 0 {
+                    out.push(Move::makeEP(from, epsq));
+                }
             }
         }
 
@@ -1107,30 +1105,11 @@ pub fn generate_qcaptures(&self, board: &mut Board)-> Vec<Move>{
 }
   #[inline(always)]
     pub fn is_square_attacked_by(&self, board: &Board, occ: u64, by_side: u8, sq: u8) -> bool {
-        let sq_bb = 1u64 << (sq as u64);
-
         // --- Pawns ---
-        // by_side == 0 => white pawns attack NE/NW (from their perspective).
-        // by_side == 1 => black pawns attack SE/SW.
-        //
-        // We want: "is sq attacked by pawns of by_side?"
-        // Equivalent: "is there a pawn of by_side on a square that attacks sq?"
+        // A pawn of by_side attacks sq exactly when a pawn of the other side on sq
+        // would attack the pawn's square.
         let pawns = board.pieces[if by_side == 0 { PieceIndex::P.index() } else { PieceIndex::p.index() }];
-
-        if by_side == 0 {
-            // White pawn attacks: from pawn square -> +7/+9.
-            // So attackers to sq are at sq-7 and sq-9.
-            // Use shifts on sq_bb to find those pawn squares.
-            let from_se = (sq_bb >> 7) & !constlib::filesmasks[7]; // not from h-file
-            let from_sw = (sq_bb >> 9) & !constlib::filesmasks[0]; // not from a-file
-            if (pawns & (from_se | from_sw)) != 0 { return true; }
-        } else {
-            // Black pawn attacks: from pawn square -> -7/-9.
-            // Attackers to sq are at sq+7 and sq+9.
-            let from_ne = (sq_bb << 7) & !constlib::filesmasks[7]; // mask h-file
-            let from_nw = (sq_bb << 9) & !constlib::filesmasks[0]; // mask a-file
-            if (pawns & (from_ne | from_nw)) != 0 { return true; }
-        }
+        if (pawns & self.pawnattacks[(by_side ^ 1) as usize][sq as usize]) != 0 { return true; }
 
         // --- Knights ---
         let knights = board.pieces[if by_side == 0 { PieceIndex::N.index() } else { PieceIndex::n.index() }];
@@ -1145,17 +1124,14 @@ pub fn generate_qcaptures(&self, board: &mut Board)-> Vec<Move>{
         let queens  = board.pieces[if by_side == 0 { PieceIndex::Q.index() } else { PieceIndex::q.index() }];
         let diag_attackers = bishops | queens;
 
-        // Use your existing diagonal slider attack for a single square:
-        // IMPORTANT: replace `self.bishop_attacks(sq, occ)` with your actual API.
-        let bishop_rays = constlib::compute_bishop(sq as i8, occ);
+        let bishop_rays = self.bishop_attacks(sq, occ);
         if (diag_attackers & bishop_rays) != 0 { return true; }
 
         // --- Rooks / Queens (orthogonals) ---
         let rooks  = board.pieces[if by_side == 0 { PieceIndex::R.index() } else { PieceIndex::r.index() }];
         let ortho_attackers = rooks | queens;
 
-        // Replace `self.rook_attacks(sq, occ)` with your actual API.
-        let rook_rays = constlib::compute_rook(sq as i8, occ);
+        let rook_rays = self.rook_attacks(sq, occ);
         if (ortho_attackers & rook_rays) != 0 { return true; }
 
         false
@@ -1166,15 +1142,27 @@ pub fn generate_qcaptures(&self, board: &mut Board)-> Vec<Move>{
           for j in 0..64_i8 {
               let i_bb: u64 = 1_u64 << i;
               let j_bb: u64 = 1_u64 << j;
-              if constlib::compute_rook(i, 0) & j_bb != 0 {
+              if self.rook[i as usize] & j_bb != 0 {
                   self.line_between[i as usize][j as usize] |=
-                      (constlib::compute_rook(j,0) & constlib::compute_rook(i,0)) | i_bb | j_bb;
-              } else if constlib::compute_bishop(i, 0) & j_bb != 0 {
+                      (self.rook[j as usize] & self.rook[i as usize]) | i_bb | j_bb;
+              } else if self.bishop[i as usize] & j_bb != 0 {
                   self.line_between[i as usize][j as usize] |=
-                      (constlib::compute_bishop(j,0) & constlib::compute_bishop(i,0)) | i_bb | j_bb;
+                      (self.bishop[j as usize] & self.bishop[i as usize]) | i_bb | j_bb;
               } else {
                   self.line_between[i as usize][j as usize] = 0;
               }
+              // squares on a line are ordered by index, so between and ray are
+              // just the part of the line on the right side of each end
+              let line = self.line_between[i as usize][j as usize];
+              let below_i = i_bb - 1;
+              let below_j = j_bb - 1;
+              if i < j {
+                  self.between[i as usize][j as usize] = line & below_j & !below_i & !i_bb;
+                  self.ray[i as usize][j as usize] = line & !below_i & !i_bb;
+              } else if i > j {
+                  self.between[i as usize][j as usize] = line & below_i & !below_j & !j_bb;
+                  self.ray[i as usize][j as usize] = line & below_i;
+              }
           }
       }
     }
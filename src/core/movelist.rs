@@ -0,0 +1,141 @@
+use std::ops::{Deref, DerefMut};
+
+use crate::core::Move;
+
+/// No legal chess position has more than 218 moves.
+pub const MAX_MOVES: usize = 256;
+
+/// Fixed-capacity move buffer that lives on the stack, so generating moves
+/// at a node never touches the heap. Derefs to `[Move]` for slice methods.
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self { moves: [Move::new(); MAX_MOVES], len: 0 }
+    }
+
+    #[inline(always)]
+    pub fn push(&mut self, mv: Move) {
+        debug_assert!(self.len < MAX_MOVES, "MoveList overflow");
+        self.moves[self.len] = mv;
+        self.len += 1;
+    }
+
+    #[inline(always)]
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Removes the move at `idx` in O(1) by moving the last move into its place.
+    #[inline(always)]
+    pub fn swap_remove(&mut self, idx: usize) -> Move {
+        assert!(idx < self.len, "MoveList::swap_remove index {} out of bounds ({})", idx, self.len);
+        let mv = self.moves[idx];
+        self.len -= 1;
+        self.moves[idx] = self.moves[self.len];
+        mv
+    }
+
+    #[inline(always)]
+    pub fn as_slice(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+
+    #[inline(always)]
+    pub fn as_mut_slice(&mut self) -> &mut [Move] {
+        &mut self.moves[..self.len]
+    }
+
+    /// Sorts best-first by `score`, computing each score once. Stable, so equal
+    /// scores keep generation order.
+    pub fn sort_by_score<F: FnMut(Move) -> i32>(&mut self, mut score: F) {
+        let mut scores = [0i32; MAX_MOVES];
+        for (s, &m) in scores.iter_mut().zip(self.as_slice()) {
+            *s = score(m);
+        }
+        // insertion sort: lists are short and usually nearly ordered
+        for i in 1..self.len {
+            let (m, s) = (self.moves[i], scores[i]);
+            let mut j = i;
+            while j > 0 && scores[j - 1] < s {
+                self.moves[j] = self.moves[j - 1];
+                scores[j] = scores[j - 1];
+                j -= 1;
+            }
+            self.moves[j] = m;
+            scores[j] = s;
+        }
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for MoveList {
+    type Target = [Move];
+    #[inline(always)]
+    fn deref(&self) -> &[Move] {
+        self.as_slice()
+    }
+}
+
+impl DerefMut for MoveList {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut [Move] {
+        self.as_mut_slice()
+    }
+}
+
+impl std::fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+pub struct IntoIter {
+    list: MoveList,
+    pos: usize,
+}
+
+impl Iterator for IntoIter {
+    type Item = Move;
+    #[inline(always)]
+    fn next(&mut self) -> Option<Move> {
+        if self.pos < self.list.len {
+            self.pos += 1;
+            Some(self.list.moves[self.pos - 1])
+        } else {
+            None
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.list.len - self.pos;
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for IntoIter {}
+
+impl IntoIterator for MoveList {
+    type Item = Move;
+    type IntoIter = IntoIter;
+    fn into_iter(self) -> IntoIter {
+        IntoIter { list: self, pos: 0 }
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = std::slice::Iter<'a, Move>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.as_slice().iter()
+    }
+}
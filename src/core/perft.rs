@@ -0,0 +1,112 @@
+use crate::core::movegen::MoveGenerator;
+use crate::core::{Board, Move};
+
+/// Counts the leaf nodes of the legal move tree to `depth`.
+/// The last ply is bulk-counted from the move list instead of being played.
+pub fn perft(board: &mut Board, depth: u8, mg: &MoveGenerator) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = mg.generate(board);
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    let mut nodes = 0;
+    for mv in moves {
+        board.push(mv, mg);
+        nodes += perft(board, depth - 1, mg);
+        board.pop();
+    }
+    nodes
+}
+
+/// Perft split by root move, in generation order. Comparing this against another
+/// engine's divide output narrows a wrong count down to a single move.
+pub fn divide(board: &mut Board, depth: u8, mg: &MoveGenerator) -> Vec<(Move, u64)> {
+    let mut out = Vec::new();
+    if depth == 0 {
+        return out;
+    }
+    for mv in mg.generate(board) {
+        board.push(mv, mg);
+        let nodes = perft(board, depth - 1, mg);
+        board.pop();
+        out.push((mv, nodes));
+    }
+    out
+}
+
+#[derive(Clone, Copy, Default)]
+struct PerftEntry {
+    key: u64,
+    depth: u8,
+    nodes: u64,
+}
+
+/// Subtree counts keyed by `Board.hash` and remaining depth. Transpositions are
+/// counted once, which makes deep perft much faster; a wrong hash shows up as a wrong total.
+pub struct PerftTable {
+    mask: usize,
+    entries: Vec<PerftEntry>,
+}
+
+impl PerftTable {
+    pub fn new_mb(megabytes: usize) -> Self {
+        let wanted = (megabytes.max(1) * 1024 * 1024) / std::mem::size_of::<PerftEntry>();
+        // largest power of two that fits
+        let len = 1usize << (usize::BITS - 1 - wanted.leading_zeros());
+        Self { mask: len - 1, entries: vec![PerftEntry::default(); len] }
+    }
+
+    #[inline(always)]
+    fn index(&self, key: u64, depth: u8) -> usize {
+        (key ^ (depth as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)) as usize & self.mask
+    }
+
+    fn probe(&self, key: u64, depth: u8) -> Option<u64> {
+        let e = &self.entries[self.index(key, depth)];
+        (e.key == key && e.depth == depth).then_some(e.nodes)
+    }
+
+    fn store(&mut self, key: u64, depth: u8, nodes: u64) {
+        let i = self.index(key, depth);
+        self.entries[i] = PerftEntry { key, depth, nodes };
+    }
+}
+
+/// `perft` with subtree counts cached in `table`.
+pub fn perft_hashed(board: &mut Board, depth: u8, mg: &MoveGenerator, table: &mut PerftTable) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    if let Some(nodes) = table.probe(board.hash, depth) {
+        return nodes;
+    }
+    let moves = mg.generate(board);
+    let nodes = if depth == 1 {
+        moves.len() as u64
+    } else {
+        let mut nodes = 0;
+        for mv in moves {
+            board.push(mv, mg);
+            nodes += perft_hashed(board, depth - 1, mg, table);
+            board.pop();
+        }
+        nodes
+    };
+    table.store(board.hash, depth, nodes);
+    nodes
+}
+
+/// Prints a divide in the usual "move: nodes" format followed by the total, the same
+/// shape as most engines' `go perft`, so outputs can be diffed line by line.
+pub fn print_divide(board: &mut Board, depth: u8, mg: &MoveGenerator, chess960: bool) -> u64 {
+    let split = divide(board, depth, mg);
+    let total = split.iter().map(|&(_, n)| n).sum();
+    for (mv, nodes) in split {
+        println!("{}: {}", mv.to_uci_mode(chess960), nodes);
+    }
+    println!();
+    println!("Nodes searched: {}", total);
+    total
+}
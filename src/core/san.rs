@@ -0,0 +1,200 @@
+use std::fmt;
+
+use crate::core::constlib;
+use crate::core::movegen::MoveGenerator;
+use crate::core::{Board, Move, PieceType};
+
+/// Why a SAN string could not be turned into a legal move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SanError {
+    /// Not shaped like SAN at all.
+    Syntax(String),
+    /// Well formed, but no legal move matches.
+    Illegal(String),
+    /// More than one legal move matches (missing disambiguation).
+    Ambiguous(String),
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SanError::Syntax(s) => write!(f, "malformed SAN '{}'", s),
+            SanError::Illegal(s) => write!(f, "illegal move '{}'", s),
+            SanError::Ambiguous(s) => write!(f, "ambiguous move '{}'", s),
+        }
+    }
+}
+
+impl std::error::Error for SanError {}
+
+fn piece_letter(c: char) -> Option<PieceType> {
+    match c {
+        'N' => Some(PieceType::N),
+        'B' => Some(PieceType::B),
+        'R' => Some(PieceType::R),
+        'Q' => Some(PieceType::Q),
+        'K' => Some(PieceType::K),
+        _ => None,
+    }
+}
+
+impl Board {
+    /// Formats a legal move in SAN ("Nbd7", "exd6", "e8=Q+", "O-O#").
+    /// The move is played and taken back to find the check/mate suffix.
+    pub fn move_to_san(&mut self, mv: Move, mg: &MoveGenerator) -> String {
+        let legal = mg.generate(self);
+        let mut san = self.san_body(mv, &legal);
+
+        self.push(mv, mg);
+        if mg.in_check(self) {
+            san.push(if mg.generate(self).is_empty() { '#' } else { '+' });
+        }
+        self.pop();
+        san
+    }
+
+    // SAN without the check suffix. `legal` is the legal move list of the current position.
+    fn san_body(&self, mv: Move, legal: &[Move]) -> String {
+        if mv.iscastle() {
+            return String::from(if mv.iskingcastle() { "O-O" } else { "O-O-O" });
+        }
+        let src = mv.getSrc();
+        let dst = mv.getDst();
+        let piece = self.piecelocs.piece_at(src).get_piece_type();
+        let mut san = String::new();
+
+        if piece == PieceType::P {
+            if mv.iscapture() {
+                san.push((b'a' + src % 8) as char);
+                san.push('x');
+            }
+            san.push_str(&constlib::squaretouci(dst));
+            if mv.isprom() {
+                san.push('=');
+                san.push(mv.prompiece().get_piece_type().to_ascii_uppercase());
+            }
+            return san;
+        }
+
+        san.push(piece.get_piece_type().to_ascii_uppercase());
+
+        // other pieces of the same kind that can also reach dst
+        let rivals: Vec<u8> = legal
+            .iter()
+            .filter(|m| {
+                !m.iscastle()
+                    && m.getDst() == dst
+                    && m.getSrc() != src
+                    && self.piecelocs.piece_at(m.getSrc()).get_piece_type() == piece
+            })
+            .map(|m| m.getSrc())
+            .collect();
+        if !rivals.is_empty() {
+            if rivals.iter().all(|&s| s % 8 != src % 8) {
+                san.push((b'a' + src % 8) as char);
+            } else if rivals.iter().all(|&s| s / 8 != src / 8) {
+                san.push((b'1' + src / 8) as char);
+            } else {
+                san.push_str(&constlib::squaretouci(src));
+            }
+        }
+
+        if mv.iscapture() {
+            san.push('x');
+        }
+        san.push_str(&constlib::squaretouci(dst));
+        san
+    }
+
+    /// Parses SAN into a legal move of this position. Check/mate suffixes and
+    /// annotation glyphs are ignored; "0-0", a missing 'x' and promotions without '=' are accepted.
+    pub fn parse_san(&mut self, san: &str, mg: &MoveGenerator) -> Result<Move, SanError> {
+        let text = san.trim().trim_end_matches(['+', '#', '!', '?']);
+        let legal = mg.generate(self);
+
+        if text == "O-O" || text == "0-0" || text == "O-O-O" || text == "0-0-0" {
+            let kingside = text.len() == 3;
+            return legal
+                .iter()
+                .copied()
+                .find(|m| m.iscastle() && m.iskingcastle() == kingside)
+                .ok_or_else(|| SanError::Illegal(san.to_string()));
+        }
+
+        let mut chars: Vec<char> = text.chars().collect();
+        if chars.is_empty() {
+            return Err(SanError::Syntax(san.to_string()));
+        }
+
+        // ---- promotion suffix ----
+        let mut promo = None;
+        if let Some(p) = chars.last().copied().and_then(piece_letter) {
+            if p == PieceType::K {
+                return Err(SanError::Syntax(san.to_string()));
+            }
+            promo = Some(p);
+            chars.pop();
+            if chars.last() == Some(&'=') {
+                chars.pop();
+            }
+        }
+
+        // ---- piece letter ----
+        let piece = match chars.first().copied().and_then(piece_letter) {
+            Some(p) => {
+                chars.remove(0);
+                p
+            }
+            None => PieceType::P,
+        };
+
+        // ---- destination square: always the last two characters ----
+        if chars.len() < 2 {
+            return Err(SanError::Syntax(san.to_string()));
+        }
+        let dst_str: String = chars[chars.len() - 2..].iter().collect();
+        let dst = constlib::square_from_string(&dst_str);
+        if dst == 64 {
+            return Err(SanError::Syntax(san.to_string()));
+        }
+        chars.truncate(chars.len() - 2);
+        if chars.last() == Some(&'x') {
+            chars.pop();
+        }
+
+        // ---- whatever is left is disambiguation ----
+        let mut from_file = None;
+        let mut from_rank = None;
+        for c in chars {
+            match c {
+                'a'..='h' if from_file.is_none() => from_file = Some(c as u8 - b'a'),
+                '1'..='8' if from_rank.is_none() => from_rank = Some(c as u8 - b'1'),
+                _ => return Err(SanError::Syntax(san.to_string())),
+            }
+        }
+
+        let mut found: Option<Move> = None;
+        for &m in legal.iter() {
+            if m.iscastle() || m.getDst() != dst {
+                continue;
+            }
+            let src = m.getSrc();
+            if self.piecelocs.piece_at(src).get_piece_type() != piece
+                || from_file.is_some_and(|f| src % 8 != f)
+                || from_rank.is_some_and(|r| src / 8 != r)
+            {
+                continue;
+            }
+            match (m.isprom(), promo) {
+                (false, None) => {}
+                (true, Some(p)) if m.prompiece() == p => {}
+                _ => continue,
+            }
+            if found.is_some() {
+                return Err(SanError::Ambiguous(san.to_string()));
+            }
+            found = Some(m);
+        }
+        found.ok_or_else(|| SanError::Illegal(san.to_string()))
+    }
+}
@@ -0,0 +1,133 @@
+use crate::core::movegen::MoveGenerator;
+use crate::core::{Board, Move, PieceType};
+
+/// Piece values for static exchange evaluation, indexed by `PieceType as usize`.
+pub const SEE_VALUE: [i32; 7] = [0, 100, 320, 330, 500, 900, 20_000];
+
+// ---- static exchange evaluation ----
+// Plays out every capture on the destination square, cheapest attacker first, with
+// each side free to stop when continuing would lose more. Sliders behind a piece that
+// has just captured join in as x-rays. Pins are ignored, and pieces recapturing onto
+// the last rank stay pawns.
+impl MoveGenerator {
+    /// Material `mv` wins (negative: loses) once all exchanges on its destination are
+    /// played out. Quiet moves score what the piece would lose by standing there.
+    pub fn see(&self, board: &Board, mv: Move) -> i32 {
+        if mv.iscastle() {
+            return 0;
+        }
+        let to = mv.getDst();
+        let (first, mut on_square, mut occ) = see_setup(board, mv);
+
+        let mut gain = [0i32; 32];
+        gain[0] = first;
+        let mut d = 0;
+        let mut side = board.turn ^ 1;
+        let mut attackers = self.attackers_to(board, to, occ) & occ;
+        while let Some((pt, sq)) = least_valuable(board, attackers, side) {
+            // the king can only recapture onto a square the other side no longer covers
+            if pt == PieceType::K && attackers & board.playerpieces[(side ^ 1) as usize] != 0 {
+                break;
+            }
+            d += 1;
+            gain[d] = on_square - gain[d - 1];
+            on_square = SEE_VALUE[pt as usize];
+            occ ^= 1u64 << sq;
+            attackers = (attackers | self.slider_attackers_to(board, to, occ)) & occ;
+            side ^= 1;
+        }
+        while d > 0 {
+            gain[d - 1] = -(-gain[d - 1]).max(gain[d]);
+            d -= 1;
+        }
+        gain[0]
+    }
+
+    /// `see(board, mv) >= threshold`, but stops as soon as the answer is known.
+    pub fn see_ge(&self, board: &Board, mv: Move, threshold: i32) -> bool {
+        if mv.iscastle() {
+            return 0 >= threshold;
+        }
+        let to = mv.getDst();
+        let (first, on_square, mut occ) = see_setup(board, mv);
+
+        // `swap` is what the side to move would have to win back for the balance
+        // to cross the threshold; `res` is whether it is crossed if play stops here
+        let mut swap = first - threshold;
+        if swap < 0 {
+            return false;
+        }
+        swap = on_square - swap;
+        if swap <= 0 {
+            return true;
+        }
+
+        let mut side = board.turn;
+        let mut attackers = self.attackers_to(board, to, occ);
+        let mut res = true;
+        loop {
+            side ^= 1;
+            attackers &= occ;
+            let Some((pt, sq)) = least_valuable(board, attackers, side) else {
+                break;
+            };
+            if pt == PieceType::K {
+                // a king capture only stands if nothing can take back
+                return if attackers & board.playerpieces[(side ^ 1) as usize] != 0 { res } else { !res };
+            }
+            res = !res;
+            swap = SEE_VALUE[pt as usize] - swap;
+            if swap < res as i32 {
+                break;
+            }
+            occ ^= 1u64 << sq;
+            attackers |= self.slider_attackers_to(board, to, occ);
+        }
+        res
+    }
+}
+
+// Gain of the move itself, value of the piece left on the destination, and the
+// occupancy once it has been played.
+#[inline(always)]
+fn see_setup(board: &Board, mv: Move) -> (i32, i32, u64) {
+    let from = mv.getSrc();
+    let to = mv.getDst();
+    let mut occ = (board.occupied & !(1u64 << from)) | (1u64 << to);
+
+    let mut gain = if mv.isep() {
+        let cap_sq = if board.turn == 0 { to - 8 } else { to + 8 };
+        occ &= !(1u64 << cap_sq);
+        SEE_VALUE[PieceType::P as usize]
+    } else if mv.iscapture() {
+        SEE_VALUE[board.piecelocs.piece_at(to).get_piece_type() as usize]
+    } else {
+        0
+    };
+    let mut on_square = SEE_VALUE[board.piecelocs.piece_at(from).get_piece_type() as usize];
+    if mv.isprom() {
+        on_square = SEE_VALUE[mv.prompiece() as usize];
+        gain += on_square - SEE_VALUE[PieceType::P as usize];
+    }
+    (gain, on_square, occ)
+}
+
+// Cheapest piece of `side` in `attackers`.
+#[inline(always)]
+fn least_valuable(board: &Board, attackers: u64, side: u8) -> Option<(PieceType, u8)> {
+    let ours = attackers & board.playerpieces[side as usize];
+    if ours == 0 {
+        return None;
+    }
+    let base = 6 * side as usize;
+    for (i, pt) in [PieceType::P, PieceType::N, PieceType::B, PieceType::R, PieceType::Q, PieceType::K]
+        .into_iter()
+        .enumerate()
+    {
+        let bb = ours & board.pieces[base + i];
+        if bb != 0 {
+            return Some((pt, bb.trailing_zeros() as u8));
+        }
+    }
+    None
+}
@@ -19,27 +19,24 @@ pub struct Undo {
     /// Previous zobrist hash.
     pub hash: u64,
 
+    /// Previous halfmove clock (fullmove number is derived from the mover's color).
+    pub halfmove_clock: u16,
+
     /// Captured piece identity (Piece::None if no capture).
     pub captured_piece: Piece,
 
     /// Square the captured piece came from (dst for normal captures, pawn square for EP).
     /// 64 means no capture.
     pub captured_sq: u8,
-        // NEW: NNUE accumulators saved before push
-    pub nnue_acc_w: [i32; 256],
-    pub nnue_acc_b: [i32; 256],
 }
 
 impl Undo {
     #[inline(always)]
-    pub fn new(mv: Move, castling_rights: u8, ep_square: u8, hash: u64,
-        nnue_acc_w: [i32;256], nnue_acc_b: [i32;256]) -> Self {
+    pub fn new(mv: Move, castling_rights: u8, ep_square: u8, hash: u64, halfmove_clock: u16) -> Self {
         Self {
-            mv, castling_rights, ep_square, hash,
+            mv, castling_rights, ep_square, hash, halfmove_clock,
             captured_piece: Piece::None,
             captured_sq: 64,
-            nnue_acc_w,
-            nnue_acc_b,
         }
     }
 }
\ No newline at end of file
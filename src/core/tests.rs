@@ -0,0 +1,1057 @@
+use crate::core::perft::{self, PerftTable};
+use crate::core::{movegen::MoveGenerator, Board, Move};
+
+fn check_perft(fen: &str, expected: &[u64]) {
+    let mg = MoveGenerator::new();
+    let mut board = Board::try_from_fen(fen).unwrap();
+    for (depth, &nodes) in expected.iter().enumerate() {
+        let depth = depth as u8 + 1;
+        assert_eq!(perft::perft(&mut board, depth, &mg), nodes, "{} depth {}", fen, depth);
+    }
+    assert_eq!(board.to_fen().split(' ').next(), fen.split(' ').next());
+}
+
+// Standard perft positions plus the usual edge cases for en passant pins,
+// castling through/into check, promotions and stalemate. Depths are kept low
+// enough for a debug build; the last count is the one that matters.
+const PERFT_SUITE: [(&str, u8, u64); 21] = [
+    ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 4, 197281),
+    ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 3, 97862),
+    ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 5, 674624),
+    ("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", 4, 422333),
+    ("r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1", 4, 422333),
+    ("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", 3, 62379),
+    ("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", 3, 89890),
+    // en passant that would expose the king, in both directions
+    ("3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1", 5, 185429),
+    ("8/8/4k3/8/2p5/8/B2P2K1/8 w - - 0 1", 5, 135655),
+    // en passant capture gives check
+    ("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1", 5, 206379),
+    // castling gives check, castling rights lost to a rook capture, castling through check
+    ("5k2/8/8/8/8/8/8/4K2R w K - 0 1", 5, 120330),
+    ("3k4/8/8/8/8/8/8/R3K3 w Q - 0 1", 5, 141077),
+    ("r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1", 3, 27826),
+    ("r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1", 3, 50509),
+    // promotions out of and into check
+    ("2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1", 5, 266199),
+    ("8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1", 4, 31961),
+    ("4k3/1P6/8/8/8/8/K7/8 w - - 0 1", 5, 38983),
+    ("8/P1k5/K7/8/8/8/8/8 w - - 0 1", 5, 18135),
+    // stalemate and checkmate at the leaves
+    ("K1k5/8/P7/8/8/8/8/8 w - - 0 1", 6, 2217),
+    ("8/k1P5/8/1K6/8/8/8/8 w - - 0 1", 6, 43261),
+    ("8/8/2k5/5q2/5n2/8/5K2/8 b - - 0 1", 4, 23527),
+];
+
+#[test]
+fn perft_suite() {
+    let mg = MoveGenerator::new();
+    for (fen, depth, nodes) in PERFT_SUITE {
+        let mut board = Board::try_from_fen(fen).unwrap();
+        assert_eq!(perft::perft(&mut board, depth, &mg), nodes, "{} depth {}", fen, depth);
+        assert_eq!(board.to_fen(), fen, "board not restored");
+    }
+}
+
+#[test]
+fn perft_divide_adds_up() {
+    let mg = MoveGenerator::new();
+    let mut board = Board::try_from_fen(PERFT_SUITE[1].0).unwrap();
+    let split = perft::divide(&mut board, 3, &mg);
+    assert_eq!(split.len(), 48);
+    assert_eq!(split.iter().map(|&(_, n)| n).sum::<u64>(), 97862);
+}
+
+#[test]
+fn perft_hashed_matches_plain() {
+    let mg = MoveGenerator::new();
+    let mut table = PerftTable::new_mb(4);
+    for (fen, depth, nodes) in [PERFT_SUITE[2], PERFT_SUITE[3], PERFT_SUITE[12]] {
+        let mut board = Board::try_from_fen(fen).unwrap();
+        assert_eq!(perft::perft_hashed(&mut board, depth, &mg, &mut table), nodes, "{}", fen);
+    }
+}
+
+// Chess960 reference counts (Shredder-FEN castling fields)
+const PERFT_960: [(&str, [u64; 3]); 11] = [
+    ("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", [21, 528, 12189]),
+    ("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9", [21, 807, 18002]),
+    ("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9", [20, 479, 10471]),
+    ("qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9", [22, 593, 13440]),
+    ("1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9", [28, 1120, 31058]),
+    ("qnbnr1kr/ppp1b1pp/4p3/3p1p2/8/2NPP3/PPP1BPPP/QNB1R1KR w HEhe - 1 9", [29, 899, 26578]),
+    ("q1bnrkr1/ppppp2p/2n2p2/4b1p1/2NP4/8/PPP1PPPP/QNB1RRKB w ge - 1 9", [30, 860, 24566]),
+    ("qbn1brkr/ppp1p1p1/2n4p/3p1p2/P7/6PP/QPPPPP2/1BNNBRKR w HFhf - 0 9", [25, 635, 17054]),
+    ("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9", [21, 528, 12189]),
+    ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1", [20, 400, 8902]),
+    ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", [48, 2039, 97862]),
+];
+
+#[test]
+fn perft_chess960() {
+    for (fen, expected) in PERFT_960 {
+        check_perft(fen, &expected);
+    }
+}
+
+#[test]
+fn chess960_castling_field_round_trip() {
+    // X-FEN letters only where K/Q would pick a different rook
+    for (fen, field) in [
+        ("4k3/8/8/8/8/8/8/4KR1R w H - 0 1", "K"),
+        ("4k3/8/8/8/8/8/8/4KR1R w F - 0 1", "F"),
+        ("rr2k2r/8/8/8/8/8/8/R3K2R b KQkb - 0 1", "KQkb"),
+    ] {
+        assert_eq!(Board::try_from_fen(fen).unwrap().castling_field(), field);
+    }
+    let h = Board::try_from_fen("4k3/8/8/8/8/8/8/4KR1R w H - 0 1").unwrap();
+    let f = Board::try_from_fen("4k3/8/8/8/8/8/8/4KR1R w F - 0 1").unwrap();
+    assert_ne!(h.hash, f.hash);
+}
+
+#[test]
+fn chess960_castling_rook_shields_king() {
+    // queen-side castling leaves the king on c1, exposed to the a1 rook once b1 is vacated
+    let mut board = Board::try_from_fen("4k3/8/8/8/8/8/8/rRK5 w B - 0 1").unwrap();
+    let mg = MoveGenerator::new();
+    assert!(mg.generate(&mut board).iter().all(|m| !m.iscastle()));
+}
+
+#[test]
+fn null_move_round_trip() {
+    let fen = "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3";
+    let mut board = Board::try_from_fen(fen).unwrap();
+    board.push_null();
+    assert_eq!(board.turn, 1);
+    assert_eq!(board.ep_square, 64);
+    assert_eq!(board.hash, Board::compute_hash(&board));
+    assert_eq!(board.to_fen(), "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 1 3");
+    board.pop_null();
+    assert_eq!(board.to_fen(), fen);
+    assert_eq!(board.hash, Board::compute_hash(&board));
+}
+
+#[test]
+fn see_exchanges() {
+    let mg = MoveGenerator::new();
+    for (fen, uci, value) in [
+        ("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1", "e1e5", 100),
+        ("1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1", "d3e5", -220),
+        ("4k3/8/2p5/3p4/4P3/8/8/4K3 w - - 0 1", "e4d5", 0),
+        // the rook behind joins in as an x-ray
+        ("3rk3/3r4/8/3p4/8/8/3R4/3RK3 w - - 0 1", "d2d5", -400),
+        ("4k3/8/8/3q4/4K3/8/8/8 w - - 0 1", "e4d5", 900),
+    ] {
+        let mut board = Board::try_from_fen(fen).unwrap();
+        let mv = mg.generate(&mut board).iter().copied().find(|m| m.to_uci() == uci).unwrap();
+        assert_eq!(mg.see(&board, mv), value, "{} {}", fen, uci);
+        for t in [value - 1, value, value + 1] {
+            assert_eq!(mg.see_ge(&board, mv, t), value >= t, "{} {} >= {}", fen, uci, t);
+        }
+    }
+}
+
+#[test]
+fn gives_check_matches_push() {
+    fn walk(board: &mut Board, mg: &MoveGenerator, depth: u8) {
+        let moves = mg.generate(board);
+        let ci = mg.check_info(board);
+        for mv in moves {
+            let predicted = mg.gives_check_with(board, mv, &ci);
+            board.push(mv, mg);
+            assert_eq!(predicted, mg.in_check(board), "{} {}", board.to_fen(), mv.to_uci());
+            if depth > 1 {
+                walk(board, mg, depth - 1);
+            }
+            board.pop();
+        }
+    }
+    let mg = MoveGenerator::new();
+    // discovered checks, en passant, promotions and castling (including Chess960)
+    for fen in [
+        PERFT_SUITE[1].0,
+        PERFT_SUITE[2].0,
+        PERFT_SUITE[3].0,
+        PERFT_SUITE[9].0,
+        PERFT_SUITE[14].0,
+        PERFT_960[0].0,
+    ] {
+        walk(&mut Board::try_from_fen(fen).unwrap(), &mg, 3);
+    }
+}
+
+#[test]
+fn single_move_legality_matches_generator() {
+    let mg = MoveGenerator::new();
+    // pins, en passant discovering a rank attack, castling through check, Chess960 castling
+    for fen in [
+        PERFT_SUITE[1].0,
+        PERFT_SUITE[3].0,
+        PERFT_SUITE[7].0,
+        PERFT_SUITE[9].0,
+        PERFT_SUITE[12].0,
+        PERFT_960[0].0,
+    ] {
+        let mut board = Board::try_from_fen(fen).unwrap();
+        let legal = mg.generate(&mut board);
+        // every encoding, so garbage from a TT collision is covered too
+        for data in 0..=u16::MAX {
+            let mv = Move::from_u16(data);
+            let ok = mg.is_pseudo_legal(&board, mv) && mg.is_legal(&board, mv);
+            assert_eq!(ok, legal.contains(&mv), "{} {}", fen, mv.to_uci());
+        }
+    }
+}
+
+#[test]
+fn board_clone_eq_hash_display() {
+    use std::collections::HashSet;
+    let mg = MoveGenerator::new();
+    let play = |board: &mut Board, moves: &[&str]| {
+        for uci in moves {
+            let mv = mg.generate(board).iter().copied().find(|m| m.to_uci() == *uci).unwrap();
+            board.push(mv, &mg);
+        }
+    };
+
+    let mut a: Board = PERFT_SUITE[0].0.parse().unwrap();
+    play(&mut a, &["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1"]);
+    // the clone still knows the game, so the coming repetition is seen and moves can be taken back
+    let mut b = a.clone();
+    play(&mut b, &["f6g8"]);
+    assert!(b.is_repetition());
+    b.pop();
+    assert!(b == a);
+
+    // same position by another move order: equal and hashed alike, whatever the history
+    let mut c = Board::try_from_fen(PERFT_SUITE[0].0).unwrap();
+    play(&mut c, &["b1c3", "g8f6", "c3b1"]);
+    assert!(c == a);
+    assert_ne!(c.to_fen(), a.to_fen()); // move counters differ
+    let set: HashSet<Board> = [a.clone(), c.clone(), Board::try_from_fen(PERFT_SUITE[1].0).unwrap()].into_iter().collect();
+    assert_eq!(set.len(), 2);
+
+    assert_eq!(a.to_string(), a.to_fen());
+    assert!(a.to_string().parse::<Board>().unwrap() == a);
+    assert!("not a fen".parse::<Board>().is_err());
+}
+
+#[test]
+fn attack_tables_and_queries() {
+    let mg = MoveGenerator::new();
+    for a in 0..64u8 {
+        for b in 0..64u8 {
+            let (ai, bi) = (a as usize, b as usize);
+            let line = mg.line_between[ai][bi];
+            if a == b || line == 0 {
+                assert_eq!(mg.between[ai][bi] | mg.ray[ai][bi], 0, "{} {}", a, b);
+                continue;
+            }
+            let magic_between = if mg.rook[ai] & (1u64 << b) != 0 {
+                mg.rook_attacks(a, 1u64 << b) & mg.rook_attacks(b, 1u64 << a)
+            } else {
+                mg.bishop_attacks(a, 1u64 << b) & mg.bishop_attacks(b, 1u64 << a)
+            };
+            assert_eq!(mg.between[ai][bi], magic_between, "{} {}", a, b);
+            assert_eq!(mg.between[ai][bi], mg.ray[ai][bi] & mg.ray[bi][ai], "{} {}", a, b);
+            assert_eq!(mg.ray[ai][bi] | mg.ray[bi][ai], line, "{} {}", a, b);
+            assert!(mg.ray[ai][bi] & (1u64 << b) != 0 && mg.ray[ai][bi] & (1u64 << a) == 0);
+        }
+    }
+
+    for (fen, _, _) in &PERFT_SUITE[..6] {
+        let board = Board::try_from_fen(fen).unwrap();
+        for sq in 0..64u8 {
+            for side in 0..2u8 {
+                let by = mg.attackers_to_by(&board, sq, board.occupied, side);
+                assert_eq!(by != 0, mg.is_square_attacked_by(&board, board.occupied, side, sq), "{} {}", fen, sq);
+            }
+        }
+    }
+
+    // rooks doubled on both sides of d5: the back ones only hit it through the front ones
+    let board = Board::try_from_fen("3rk3/3r4/8/3p4/8/8/3R4/3RK3 w - - 0 1").unwrap();
+    let bb = |sqs: &[u8]| sqs.iter().fold(0u64, |acc, &s| acc | (1u64 << s));
+    assert_eq!(mg.attackers_to(&board, 35, board.occupied), bb(&[11, 51]));
+    assert_eq!(mg.xray_attackers_to(&board, 35, board.occupied), bb(&[3, 59]));
+    assert_eq!(mg.xray_rook_attacks(3, board.occupied, bb(&[11])), bb(&[19, 27, 35]));
+    assert!(mg.aligned(3, 59, 35) && !mg.aligned(3, 59, 36));
+}
+
+#[test]
+fn uci_go_params() {
+    use crate::search::search::MAX_DEPTH;
+    use crate::uci::uci::GoParams;
+
+    let go = |line: &str| GoParams::parse(&line.split_whitespace().collect::<Vec<_>>());
+    let l = go("wtime 1000 btime 2000 winc 10 binc 20 movestogo 5 nodes 300 movetime 400").limits;
+    assert_eq!((l.wtime, l.btime, l.winc, l.binc), (Some(1000), Some(2000), Some(10), Some(20)));
+    assert_eq!((l.movestogo, l.nodes, l.movetime), (Some(5), Some(300), Some(400)));
+    assert!(l.depth.is_none() && !l.infinite);
+
+    assert_eq!(go("depth 6").limits.depth, Some(6));
+    assert_eq!(go("depth 0").limits.depth, Some(1));
+    assert_eq!(go("depth 1000").limits.depth, Some(MAX_DEPTH));
+    // a missing or non-numeric value leaves the limit unset and the next keyword alone
+    assert_eq!(go("depth").limits.depth, None);
+    let l = go("depth x infinite").limits;
+    assert_eq!(l.depth, None);
+    assert!(l.infinite);
+    let l = go("depth nodes 5").limits;
+    assert_eq!((l.depth, l.nodes), (None, Some(5)));
+    assert!(go("infinite").limits.infinite);
+    assert!(go("searchmoves e2e4 infinite").limits.infinite);
+
+    assert_eq!(go("perft 3").perft, Some(3));
+    assert_eq!(go("perft").perft, None);
+    assert_eq!(go("").perft, None);
+}
+
+#[test]
+fn uci_position_and_options() {
+    use crate::uci::Uci;
+    use std::sync::Arc;
+
+    let mut uci = Uci::new(None);
+    let fen = |uci: &Uci| uci.board.to_fen();
+
+    uci.handle("position startpos moves e2e4 e7e5 g1f3");
+    assert_eq!(fen(&uci), "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+    uci.handle("position fen 8/8/4k3/8/8/3K4/4P3/8 w - - 3 40 moves e2e4 e6d6");
+    assert_eq!(fen(&uci), "8/8/3k4/8/4P3/3K4/8/8 w - - 1 41");
+    uci.handle("position fen 8/8/4k3/8/8/3K4/4P3/8 w - - 3 40");
+    assert_eq!(fen(&uci), "8/8/4k3/8/8/3K4/4P3/8 w - - 3 40");
+
+    // an illegal move, a bad FEN or a malformed command keep the previous position
+    uci.handle("position startpos moves e2e4 e7e5 e1e3");
+    assert_eq!(fen(&uci), "8/8/4k3/8/8/3K4/4P3/8 w - - 3 40");
+    uci.handle("position fen 8/8/8/8/8/8/8/8 w - - 0 1");
+    uci.handle("position");
+    assert_eq!(fen(&uci), "8/8/4k3/8/8/3K4/4P3/8 w - - 3 40");
+    uci.handle("position startpos");
+    assert_eq!(fen(&uci), PERFT_SUITE[0].0);
+
+    // option names are case-insensitive, values keep their spelling
+    let tt = Arc::clone(&uci.search.as_ref().unwrap().tt);
+    uci.handle("setoption name hash value 1");
+    assert!(!Arc::ptr_eq(&tt, &uci.search.as_ref().unwrap().tt));
+    let tt = Arc::clone(&uci.search.as_ref().unwrap().tt);
+    uci.handle("setoption name Hash value lots");
+    assert!(Arc::ptr_eq(&tt, &uci.search.as_ref().unwrap().tt));
+
+    uci.handle("setoption name USENNUE value false");
+    uci.handle("setoption name threads value 3");
+    uci.handle("setoption name Contempt value -25");
+    uci.handle("setoption name uci_chess960 value TRUE");
+    uci.handle("setoption name RFPMargin value 90");
+    uci.handle("setoption name futilitymargin value 110");
+    uci.handle("setoption name RAZORMARGIN value 250");
+    let search = uci.search.as_ref().unwrap();
+    assert!(!search.use_nnue);
+    assert_eq!((search.threads, search.contempt), (3, -25));
+    assert!(search.chess960);
+    assert_eq!((search.margins.rfp, search.margins.futility, search.margins.razor), (90, 110, 250));
+
+    uci.handle("setoption name Threads value many");
+    uci.handle("setoption name NoSuchOption value 1");
+    assert_eq!(uci.search.as_ref().unwrap().threads, 3);
+}
+
+#[test]
+fn draw_rules() {
+    use crate::core::cli::uci_to_move;
+    use crate::search::search::MATE_WINDOW;
+    use crate::search::Search;
+
+    let mg = MoveGenerator::new();
+
+    // knights out and back: the start position comes back after 4 and 8 plies
+    let mut board = Board::try_from_fen(PERFT_SUITE[0].0).unwrap();
+    for round in 1..=2 {
+        for uci in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+            assert_eq!(board.is_repetition(), round == 2);
+            let mv = uci_to_move(&mut board, &mg, uci).unwrap();
+            board.push(mv, &mg);
+        }
+        assert!(board.is_repetition());
+        assert_eq!(board.is_threefold_repetition(), round == 2);
+    }
+    // an irreversible move hides everything before it
+    let mv = uci_to_move(&mut board, &mg, "e2e4").unwrap();
+    board.push(mv, &mg);
+    assert!(!board.is_repetition());
+
+    // fifty moves: the hundredth quiet ply draws, unless it mates
+    assert!(!Board::try_from_fen("6k1/8/8/8/8/8/8/R5K1 w - - 99 60").unwrap().is_fifty_move_draw());
+    assert!(Board::try_from_fen("6k1/8/8/8/8/8/8/R5K1 w - - 100 60").unwrap().is_fifty_move_draw());
+    let mut board = Board::try_from_fen("6k1/8/8/8/8/8/8/R5K1 w - - 99 60").unwrap();
+    assert_eq!(Search::new(false).search_iterative(&mut board, 3, &mg).1, 0);
+    let mut board = Board::try_from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 99 60").unwrap();
+    let (best, score) = Search::new(false).search_iterative(&mut board, 3, &mg);
+    assert_eq!(best.to_uci(), "a1a8");
+    assert!(score > MATE_WINDOW);
+
+    for (fen, dead) in [
+        ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", true),
+        ("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1", true),
+        ("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1", true),
+        // bishops on the same square color, any side
+        ("2b1k3/8/8/8/8/8/8/2B1KB2 w - - 0 1", false),
+        ("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1", true),
+        ("2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/8/1NB1K3 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/8/NN2K3 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/P7/4K3 w - - 0 1", false),
+    ] {
+        assert_eq!(Board::try_from_fen(fen).unwrap().is_insufficient_material(), dead, "{}", fen);
+    }
+
+    // contempt: a draw is worth -contempt to the engine's side, +contempt to the other
+    let mut search = Search::new(false);
+    search.contempt = 30;
+    let mut board = Board::try_from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    assert_eq!(search.draw_score(&board), -30);
+    let mv = uci_to_move(&mut board, &mg, "e1e2").unwrap();
+    board.push(mv, &mg);
+    assert_eq!(search.draw_score(&board), 30);
+    board.pop();
+    assert_eq!(search.search_iterative(&mut board, 3, &mg).1, -30);
+    let mut board = Board::try_from_fen("4k3/8/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+    assert_eq!(search.search_iterative(&mut board, 3, &mg).1, -30);
+}
+
+#[test]
+fn fen_errors() {
+    use crate::core::fen::FenError;
+
+    const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR";
+    let cases: Vec<(String, FenError)> = vec![
+        (format!("{} w KQkq", START), FenError::FieldCount(3)),
+        (format!("{} w KQkq - 0 1 x", START), FenError::FieldCount(7)),
+        ("rnbqkbnr/pppppppp/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string(), FenError::RankCount(7)),
+        ("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string(), FenError::RankLength { rank: 6, files: 9 }),
+        ("rnbqkbnr/ppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string(), FenError::RankLength { rank: 7, files: 7 }),
+        ("rnbqkbnr/pppppppp/8/8/4X3/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string(), FenError::UnknownPiece('X')),
+        (format!("{} x KQkq - 0 1", START), FenError::SideToMove("x".to_string())),
+        (format!("{} w KQkx - 0 1", START), FenError::CastlingField("KQkx".to_string())),
+        (format!("{} w KQKQ - 0 1", START), FenError::CastlingField("KQKQ".to_string())),
+        (format!("{} w KQkq e9 0 1", START), FenError::EpField("e9".to_string())),
+        // two bytes but one character
+        (format!("{} w KQkq é 0 1", START), FenError::EpField("é".to_string())),
+        (format!("{} w KQkq - x 1", START), FenError::HalfmoveClock("x".to_string())),
+        (format!("{} w KQkq - 0 0", START), FenError::FullmoveNumber("0".to_string())),
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQQBNR w kq - 0 1".to_string(), FenError::KingCount { color: 0, count: 0 }),
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNP w kq - 0 1".to_string(), FenError::PawnOnBackRank(7)),
+        ("4k3/8/8/8/8/8/8/4R1K1 w - - 0 1".to_string(), FenError::SideNotToMoveInCheck),
+        ("4k3/8/8/8/8/8/8/4K3 w K - 0 1".to_string(), FenError::ImpossibleCastling('K')),
+        (format!("{} w KQkq e6 0 1", START), FenError::ImpossibleEpSquare(44)),
+    ];
+    for (fen, err) in cases {
+        assert_eq!(Board::try_from_fen(&fen).err(), Some(err), "{}", fen);
+    }
+}
+
+#[test]
+fn san_formats_and_parses() {
+    use crate::core::cli::uci_to_move;
+    use crate::core::SanError;
+
+    let mg = MoveGenerator::new();
+    // (fen, uci, san): formatting gives `san` and parsing it gives the move back
+    let cases = [
+        // file, rank and full-square disambiguation
+        ("7k/8/8/8/8/8/K7/R5R1 w - - 0 1", "a1d1", "Rad1"),
+        ("7k/8/8/R7/8/8/8/R5K1 w - - 0 1", "a1a3", "R1a3"),
+        ("8/7k/8/8/8/Q7/8/Q1Q3K1 w - - 0 1", "a1b2", "Qa1b2"),
+        // captures
+        ("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1", "e4d5", "exd5"),
+        ("4k3/8/8/4p3/8/5N2/8/4K3 w - - 0 1", "f3e5", "Nxe5"),
+        // promotions, with check
+        ("3r3k/4P3/8/8/8/8/8/4K3 w - - 0 1", "e7e8q", "e8=Q+"),
+        ("3r3k/4P3/8/8/8/8/8/4K3 w - - 0 1", "e7e8n", "e8=N"),
+        ("3r3k/4P3/8/8/8/8/8/4K3 w - - 0 1", "e7d8q", "exd8=Q+"),
+        // en passant
+        ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6", "exd6"),
+        // mate
+        ("r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4", "h5f7", "Qxf7#"),
+    ];
+    for (fen, uci, san) in cases {
+        let mut board = Board::try_from_fen(fen).unwrap();
+        let mv = uci_to_move(&mut board, &mg, uci).unwrap();
+        assert_eq!(board.move_to_san(mv, &mg), san, "{}", fen);
+        assert_eq!(board.parse_san(san, &mg), Ok(mv), "{}", fen);
+    }
+
+    // lenient input
+    let mut board = Board::try_from_fen("3r3k/4P3/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let e8q = uci_to_move(&mut board, &mg, "e7e8q").unwrap();
+    assert_eq!(board.parse_san("e8Q", &mg), Ok(e8q));
+    assert_eq!(board.parse_san("e8=Q!?", &mg), Ok(e8q));
+    let mut board = Board::try_from_fen("4k3/8/8/4p3/8/5N2/8/4K3 w - - 0 1").unwrap();
+    assert_eq!(board.parse_san("Ne5", &mg), board.parse_san("Nxe5", &mg));
+
+    // castling, standard and Chess960, both spellings
+    for fen in ["r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "rk5r/8/8/8/8/8/8/RK5R w HAha - 0 1"] {
+        let mut board = Board::try_from_fen(fen).unwrap();
+        let castles: Vec<Move> = mg.generate(&mut board).iter().copied().filter(|m| m.iscastle()).collect();
+        assert_eq!(castles.len(), 2, "{}", fen);
+        for m in castles {
+            let (san, alt) = if m.iskingcastle() { ("O-O", "0-0") } else { ("O-O-O", "0-0-0") };
+            assert_eq!(board.move_to_san(m, &mg), san, "{}", fen);
+            assert_eq!(board.parse_san(san, &mg), Ok(m));
+            assert_eq!(board.parse_san(alt, &mg), Ok(m));
+        }
+    }
+
+    let mut board = Board::try_from_fen("7k/8/8/8/8/8/K7/R5R1 w - - 0 1").unwrap();
+    assert_eq!(board.parse_san("Rd1", &mg), Err(SanError::Ambiguous("Rd1".to_string())));
+    assert_eq!(board.parse_san("Rd2", &mg), Err(SanError::Illegal("Rd2".to_string())));
+    assert_eq!(board.parse_san("O-O", &mg), Err(SanError::Illegal("O-O".to_string())));
+    for bad in ["", "Rz1", "e9", "Ke8=K", "R1a1d1"] {
+        assert_eq!(board.parse_san(bad, &mg), Err(SanError::Syntax(bad.to_string())));
+    }
+}
+
+#[test]
+fn pgn_reads_annotated_games() {
+    use crate::pgn::{parse_pgn, GameResult, PgnError};
+
+    let mg = MoveGenerator::new();
+    let text = r#"[Event "Club \"A\" \\ open"]
+[White "Alice"]
+[Result "1-0"]
+
+{Opening} 1. e4 e5 {main} (1... c5 $14 {Sicilian} (1... e6 2. d4) 2. Nf3) 2. Nf3! Nc6?! $10 3. Bb5 1-0
+
+[Event "Second"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 b - - 0 40"]
+
+40... Kd7 41. e4 *
+"#;
+    let games = parse_pgn(text, &mg).unwrap();
+    assert_eq!(games.len(), 2);
+
+    let g = &games[0];
+    assert_eq!(g.tag("Event"), Some(r#"Club "A" \ open"#));
+    assert_eq!(g.result, GameResult::WhiteWins);
+    let sans: Vec<&str> = g.moves.iter().map(|n| n.san.as_str()).collect();
+    assert_eq!(sans, ["e4", "e5", "Nf3", "Nc6", "Bb5"]);
+    assert_eq!(g.moves[0].comment_before.as_deref(), Some("Opening"));
+    assert_eq!(g.moves[1].comment.as_deref(), Some("main"));
+    assert_eq!(g.moves[2].nags, [1]);
+    assert_eq!(g.moves[3].nags, [6, 10]);
+    // the Sicilian is an alternative to 1... e5, with a French nested inside it
+    let sicilian = &g.moves[1].variations[0];
+    assert_eq!(sicilian[0].san, "c5");
+    assert_eq!(sicilian[0].nags, [14]);
+    assert_eq!(sicilian[0].comment.as_deref(), Some("Sicilian"));
+    assert_eq!(sicilian[0].variations[0].iter().map(|n| n.san.as_str()).collect::<Vec<_>>(), ["e6", "d4"]);
+    assert_eq!(sicilian[1].san, "Nf3");
+
+    let g = &games[1];
+    assert_eq!(g.start_board().unwrap().fullmove_number, 40);
+    assert_eq!(g.replay(&mg).unwrap().to_fen(), "8/3k4/8/8/4P3/8/8/4K3 b - e3 0 41");
+    assert_eq!(g.result, GameResult::Unknown);
+
+    // write and read back
+    let again = parse_pgn(&crate::pgn::write_pgn(&games), &mg).unwrap();
+    assert_eq!(again, games);
+    assert!(games[1].to_pgn().contains("40... Kd7 41. e4 *"));
+
+    // comments with no move to attach to survive
+    let g = &parse_pgn("{only a comment} 1-0", &mg).unwrap()[0];
+    assert_eq!(g.comment.as_deref(), Some("only a comment"));
+    assert_eq!(parse_pgn(&g.to_pgn(), &mg).unwrap()[0], *g);
+    let g = &parse_pgn("1. e4 ( {c} ) *", &mg).unwrap()[0];
+    assert_eq!(g.moves[0].comment.as_deref(), Some("c"));
+
+    let syntax = |text: &str| matches!(parse_pgn(text, &mg), Err(PgnError::Syntax { .. }));
+    assert!(syntax("[Event \"x\"\n1. e4 *"));
+    assert!(syntax("[Event x]\n1. e4 *"));
+    assert!(syntax("[Event \"x]\n1. e4 *"));
+    assert!(syntax("1. e4 {open *"));
+    assert!(syntax("1. e4 $300 *"));
+    assert!(syntax("1. e4!!? *"));
+    assert!(syntax("$1 1. e4 *"));
+    assert!(syntax("( 1. e4 ) *"));
+    assert!(syntax("1. e4 ) *"));
+    assert!(syntax("1. e4 ( 1. d4 *"));
+    assert!(syntax("1. e4 ( 1. d4"));
+    assert!(matches!(parse_pgn("1. e4 e5 2. Ke3 *", &mg), Err(PgnError::Move { line: 1, .. })));
+    assert!(matches!(
+        parse_pgn("[FEN \"8/8/8/8/8/8/8/8 w - - 0 1\"]\n\n*", &mg),
+        Err(PgnError::Fen { line: 3, .. })
+    ));
+}
+
+#[test]
+fn pgn_wraps_movetext() {
+    use crate::pgn::{parse_pgn, Game};
+
+    let mg = MoveGenerator::new();
+    let mut game = Game::new();
+    let mut board = game.start_board().unwrap();
+    for _ in 0..20 {
+        for uci in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+            let mv = crate::core::cli::uci_to_move(&mut board, &mg, uci).unwrap();
+            game.push_move(&mut board, mv, &mg);
+        }
+    }
+    game.moves[3].comment = Some("a long comment that has to be broken across lines like any move".to_string());
+    let text = game.to_pgn();
+    let movetext: Vec<&str> = text.lines().skip_while(|l| !l.is_empty()).filter(|l| !l.is_empty()).collect();
+    assert!(movetext.len() > 1);
+    assert!(movetext.iter().all(|l| l.len() <= 79));
+    // a line only breaks where the next word would not fit
+    for pair in movetext.windows(2) {
+        assert!(pair[0].len() + 1 + pair[1].split(' ').next().unwrap().len() > 79);
+    }
+    assert_eq!(parse_pgn(&text, &mg).unwrap()[0], game);
+}
+
+#[test]
+fn magic_attacks_match_ray_walkers() {
+    use crate::core::{constlib, magic};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(0x3A61C);
+    for sq in 0..64u8 {
+        for i in 0..20_000 {
+            // sparse, dense and uniform boards
+            let occ = match i % 3 {
+                0 => rng.random::<u64>() & rng.random::<u64>() & rng.random::<u64>(),
+                1 => rng.random::<u64>() | rng.random::<u64>(),
+                _ => rng.random::<u64>(),
+            };
+            assert_eq!(magic::bishop_attacks(sq, occ), constlib::ray_bishop(sq as i8, occ), "bishop {} {:#x}", sq, occ);
+            assert_eq!(magic::rook_attacks(sq, occ), constlib::ray_rook(sq as i8, occ), "rook {} {:#x}", sq, occ);
+        }
+    }
+}
+
+#[test]
+fn move_list_push_iterate_sort() {
+    use crate::core::movelist::{MoveList, MAX_MOVES};
+
+    // distinct for every i < 256
+    let mv = |i: usize| Move::makeQuiet((i % 64) as u8, (i / 64) as u8);
+    let mut list = MoveList::new();
+    assert!(list.is_empty());
+    for i in 0..MAX_MOVES {
+        list.push(mv(i));
+    }
+    assert_eq!(list.len(), MAX_MOVES);
+    assert!(list.iter().enumerate().all(|(i, &m)| m == mv(i)));
+    let borrowed: Vec<Move> = (&list).into_iter().copied().collect();
+    let mut owned = list.clone().into_iter();
+    assert_eq!(owned.len(), MAX_MOVES);
+    assert_eq!(owned.by_ref().take(2).collect::<Vec<_>>(), [mv(0), mv(1)]);
+    assert_eq!(owned.len(), MAX_MOVES - 2);
+    assert_eq!(borrowed[2..], owned.collect::<Vec<_>>()[..]);
+
+    list.clear();
+    assert!(list.is_empty());
+    for i in 0..5 {
+        list.push(mv(i));
+    }
+    // the last move fills the hole
+    assert_eq!(list.swap_remove(1), mv(1));
+    assert_eq!(list[..], [mv(0), mv(4), mv(2), mv(3)]);
+    assert_eq!(list.swap_remove(3), mv(3));
+    assert_eq!(list[..], [mv(0), mv(4), mv(2)]);
+
+    // best first, ties keep push order
+    list.clear();
+    let scores = [1, 3, 1, 2, 3, -5, 2];
+    for i in 0..scores.len() {
+        list.push(mv(i));
+    }
+    list.sort_by_score(|m| scores[m.getSrc() as usize]);
+    assert_eq!(list[..], [mv(1), mv(4), mv(3), mv(6), mv(0), mv(2), mv(5)]);
+}
+
+#[test]
+#[should_panic]
+fn move_list_push_past_capacity_panics() {
+    use crate::core::movelist::{MoveList, MAX_MOVES};
+
+    let mut list = MoveList::new();
+    for _ in 0..=MAX_MOVES {
+        list.push(Move::new());
+    }
+}
+
+// Positions from random games out of the perft suite, for tests that want variety.
+fn random_positions(mg: &MoveGenerator, games: usize, plies: usize) -> Vec<Board> {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(0xF119_5EED);
+    let mut out = Vec::new();
+    for (fen, _, _) in PERFT_SUITE.iter().cycle().take(games) {
+        let mut board = Board::try_from_fen(fen).unwrap();
+        for _ in 0..plies {
+            out.push(board.clone_position());
+            let moves = mg.generate(&mut board);
+            if moves.is_empty() {
+                break;
+            }
+            board.push(moves[rng.random_range(0..moves.len())], mg);
+        }
+    }
+    out
+}
+
+#[test]
+fn flipped_board_is_symmetric() {
+    use crate::evaluate::evaluate;
+
+    let mg = MoveGenerator::new();
+    let start = Board::try_from_fen("r3k2r/8/8/8/3pP3/8/8/R3K2R b Kq e3 0 1").unwrap();
+    assert_eq!(start.flipped().to_fen(), "r3k2r/8/8/3Pp3/8/8/8/R3K2R w Qk e6 0 1");
+    assert!(start.flipped().flipped() == start);
+
+    // the mirror has the same move tree, castling and en passant included
+    for fen in PERFT_SUITE.iter().map(|e| e.0).chain(PERFT_960.iter().map(|e| e.0)) {
+        let mut board = Board::try_from_fen(fen).unwrap();
+        let mut flipped = board.flipped();
+        assert_eq!(flipped.hash, Board::try_from_fen(&flipped.to_fen()).unwrap().hash, "{}", fen);
+        assert_eq!(perft::perft(&mut flipped, 3, &mg), perft::perft(&mut board, 3, &mg), "{}", fen);
+    }
+
+    let positions = random_positions(&mg, 100, 40);
+    assert!(positions.len() > 1000);
+    for board in &positions {
+        assert_eq!(evaluate(board, &mg), evaluate(&board.flipped(), &mg), "{}", board.to_fen());
+    }
+}
+
+// A net with random weights of roughly trained magnitudes, for tests that need some
+// net but not a good one.
+fn random_nnue(seed: u64) -> crate::evaluate::Nnue {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (hidden, h1, h2) = (256, 32, 32);
+    let num_feat = 64 * 12 * 64 + 1;
+    let mut random = |n: usize, r: i16| -> Vec<i16> { (0..n).map(|_| rng.random_range(-r..=r)).collect() };
+    // feature rows cycle through 509 random ones: filling all of them one by one is slow
+    // in a debug build, and a prime count keeps mirrored features on different rows
+    let rows = random(509 * hidden, 32);
+    let mut emb = Vec::with_capacity(num_feat * hidden);
+    for f in 0..num_feat {
+        emb.extend_from_slice(&rows[f % 509 * hidden..][..hidden]);
+    }
+    crate::evaluate::Nnue {
+        num_feat,
+        hidden,
+        h1,
+        h2,
+        scale_emb: 16,
+        scale_fc1: 64,
+        scale_fc2: 64,
+        scale_out: 64,
+        scale_fast_out: 64,
+        emb,
+        b1: (0..hidden as i32).map(|i| i % 7 * 16).collect(),
+        fc1_w: random(h1 * 2 * hidden, 4),
+        fc1_b: vec![64; h1],
+        fc2_w: random(h2 * h1, 16),
+        fc2_b: vec![0; h2],
+        out_w: random(h2, 32),
+        out_b: 0,
+        fast_out_w: random(2 * hidden, 8),
+        fast_out_b: 0,
+    }
+}
+
+#[test]
+fn nnue_eval_is_symmetric() {
+    // the weights have no symmetry of their own, only the input layout can give it
+    let nnue = random_nnue(0x5EED_4E4E);
+    let mg = MoveGenerator::new();
+    let mut distinct = std::collections::HashSet::new();
+    for board in random_positions(&mg, 21, 20) {
+        let score = nnue.eval_cp_like(&board);
+        distinct.insert(score);
+        assert_eq!(score, nnue.eval_cp_like(&board.flipped()), "{}", board);
+        assert_eq!(nnue.eval_fast_cp_like(&board), nnue.eval_fast_cp_like(&board.flipped()), "{}", board);
+    }
+    // the net is not trivially constant
+    assert!(distinct.len() > 100);
+}
+
+#[test]
+fn search_respects_limits() {
+    use crate::search::{Search, SearchLimits};
+    use std::sync::atomic::Ordering;
+    use std::time::{Duration, Instant};
+
+    let mg = MoveGenerator::new();
+    let mut board = Board::try_from_fen(PERFT_SUITE[1].0).unwrap();
+    let legal = mg.generate(&mut board);
+    let mut search = Search::new(false);
+
+    // the node limit is exact: the node that reaches it is the last one searched
+    let (best, _) = search.search_with_limits(&mut board, &SearchLimits::nodes(5_000), &mg);
+    assert!(legal.contains(&best));
+    assert_eq!(search.nodes + search.qnodes, 5_000);
+
+    let start = Instant::now();
+    let (best, _) = search.search_with_limits(&mut board, &SearchLimits::movetime(200), &mg);
+    assert!(legal.contains(&best));
+    assert!(start.elapsed() < Duration::from_secs(2));
+
+    // stopped before it starts: still a legal answer
+    search.stop.store(true, Ordering::Relaxed);
+    let (best, _) = search.search_with_limits(&mut board, &SearchLimits::default(), &mg);
+    assert!(legal.contains(&best));
+    assert_eq!(board.to_fen(), PERFT_SUITE[1].0);
+}
+
+#[test]
+fn threaded_search_shares_tt() {
+    use crate::search::tt::{TranspositionTable, TT_LOWER};
+    use crate::search::{Search, SearchLimits};
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    let tt = TranspositionTable::new_mb(1);
+    tt.store(0xDEAD_BEEF, 7, TT_LOWER, -99_000, 0x1234);
+    let e = tt.probe(0xDEAD_BEEF);
+    assert_eq!((e.key, e.depth, e.flag, e.score, e.best), (0xDEAD_BEEF, 7, TT_LOWER, -99_000, 0x1234));
+    tt.clear();
+    assert_ne!(tt.probe(0xDEAD_BEEF).key, 0xDEAD_BEEF);
+
+    let mg = MoveGenerator::new();
+    let mut board = Board::try_from_fen(PERFT_SUITE[1].0).unwrap();
+    let legal = mg.generate(&mut board);
+    let mut search = Search::new(false);
+    search.threads = 3;
+    let (best, _) = search.search_with_limits(&mut board, &SearchLimits::depth(4), &mg);
+    assert!(legal.contains(&best));
+    assert_eq!(board.to_fen(), PERFT_SUITE[1].0);
+
+    // an infinite search with helpers running ends on `stop`
+    let stop = Arc::clone(&search.stop);
+    let stopper = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(300));
+        stop.store(true, Ordering::Relaxed);
+    });
+    let start = Instant::now();
+    let infinite = SearchLimits { infinite: true, ..SearchLimits::default() };
+    let (best, _) = search.search_with_limits(&mut board, &infinite, &mg);
+    stopper.join().unwrap();
+    assert!(start.elapsed() < Duration::from_secs(3));
+    assert!(legal.contains(&best));
+    assert_eq!(board.to_fen(), PERFT_SUITE[1].0);
+}
+
+#[test]
+fn movegen_regressions() {
+    use crate::core::constlib;
+    use crate::core::fen::FenError;
+
+    assert_eq!(constlib::filesmasks[0], 0x0101_0101_0101_0101);
+    assert_eq!(constlib::filesmasks[7], 0x8080_8080_8080_8080);
+    // the pinned e5 pawn cannot take en passant but can still take its pinner
+    check_perft("7k/8/5b2/3pP3/3K4/8/8/8 w - d6 0 1", &[6, 51, 382, 4248]);
+    // pawn attacks do not wrap around the board edge
+    assert!(Board::try_from_fen("8/8/k7/8/7P/8/8/4K3 w - - 0 1").is_ok());
+    assert!(Board::try_from_fen("8/8/7k/P7/8/8/8/4K3 w - - 0 1").is_ok());
+    assert_eq!(Board::try_from_fen("8/8/k7/1P6/8/8/8/4K3 w - - 0 1").err(), Some(FenError::SideNotToMoveInCheck));
+
+    // fxe3 would leave the h4 king open to the b4 rook: qsearch must not see it either
+    let fen = "8/2p5/3p4/KP5r/1R2Pp1k/8/6P1/8 b - e3 0 1";
+    check_perft(fen, &[16, 177, 2748]);
+    let mg = MoveGenerator::new();
+    let mut positions = random_positions(&mg, 21, 20);
+    positions.push(Board::try_from_fen(fen).unwrap());
+    for mut board in positions {
+        if mg.in_check(&board) {
+            continue;
+        }
+        let mut caps: Vec<u16> = mg.generate_qcaptures(&mut board).iter().map(|m| m.as_u16()).collect();
+        caps.sort_unstable();
+        let mut legal: Vec<u16> =
+            mg.generate(&mut board).iter().filter(|m| m.iscapture() || m.isprom()).map(|m| m.as_u16()).collect();
+        legal.sort_unstable();
+        assert_eq!(caps, legal, "{}", board.to_fen());
+    }
+}
+
+#[test]
+fn move_picker_yields_each_legal_move_once() {
+    use crate::search::movepick::MovePicker;
+    use crate::search::Search;
+
+    let mg = MoveGenerator::new();
+    let search = Search::new(false);
+    for (n, mut board) in random_positions(&mg, 21, 20).into_iter().enumerate() {
+        let legal = mg.generate(&mut board);
+        if legal.is_empty() {
+            continue;
+        }
+        let quiets: Vec<Move> = legal.iter().copied().filter(|m| m.isquiet()).collect();
+        let tt_move = legal[n % legal.len()];
+        // killers are quiet moves from this node, or stale ones the picker has to drop
+        let killers = [
+            quiets.get(n % 3).copied().unwrap_or(Move::new()),
+            quiets.get(n % 3 + 1).copied().unwrap_or(Move::from_u16(0x0fff)),
+        ];
+        let in_check = mg.in_check(&board);
+        let mut picker = MovePicker::new(tt_move, killers, in_check);
+        let mut picked = Vec::new();
+        while let Some(m) = picker.next(&search, &mut board, &mg) {
+            picked.push(m);
+        }
+        assert_eq!(picked.first(), Some(&tt_move), "{}", board.to_fen());
+
+        let mut sorted: Vec<u16> = picked.iter().map(|m| m.as_u16()).collect();
+        sorted.sort_unstable();
+        let mut expected: Vec<u16> = legal.iter().map(|m| m.as_u16()).collect();
+        expected.sort_unstable();
+        assert_eq!(sorted, expected, "{}", board.to_fen());
+
+        if !in_check {
+            // killers come ahead of every other quiet move
+            let first_other_quiet = picked
+                .iter()
+                .position(|m| m.isquiet() && *m != tt_move && !killers.contains(m))
+                .unwrap_or(picked.len());
+            for k in killers.iter().filter(|k| legal.contains(k) && **k != tt_move) {
+                assert!(picked.iter().position(|m| m == k).unwrap() < first_other_quiet, "{}", board.to_fen());
+            }
+        }
+    }
+}
+
+#[test]
+fn accumulator_stack_matches_refresh() {
+    use crate::core::PieceType;
+    use crate::evaluate::nnue::{Accumulator, AccumulatorStack};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    let nnue = random_nnue(0xACC0_57AC);
+    let mg = MoveGenerator::new();
+    let mut rng = StdRng::seed_from_u64(0xACC0_57AC);
+    // captures, promotions, en passant, castling and king moves seen along the way
+    let mut seen = [0u32; 5];
+    for (fen, _, _) in PERFT_SUITE.iter().cycle().take(84) {
+        let mut board = Board::try_from_fen(fen).unwrap();
+        let mut stack = AccumulatorStack::new();
+        stack.reset(&nnue, &board);
+        // true for null moves
+        let mut line: Vec<bool> = Vec::new();
+        for _ in 0..120 {
+            let moves = mg.generate(&mut board);
+            if !line.is_empty() && (moves.is_empty() || rng.random_range(0..4) == 0) {
+                if line.pop().unwrap() {
+                    board.pop_null();
+                } else {
+                    board.pop();
+                }
+                stack.pop();
+            } else if moves.is_empty() {
+                break;
+            } else if !mg.in_check(&board) && rng.random_range(0..8) == 0 {
+                stack.push_null();
+                board.push_null();
+                line.push(true);
+            } else {
+                // the rare moves are played whenever they come up
+                let rare: Vec<Move> = moves.iter().copied().filter(|m| m.isep() || m.iscastle() || m.isprom()).collect();
+                let mv = if !rare.is_empty() && rng.random_range(0..2) == 0 {
+                    rare[rng.random_range(0..rare.len())]
+                } else {
+                    moves[rng.random_range(0..moves.len())]
+                };
+                let king = board.piecelocs.piece_at(mv.getSrc()).get_piece_type() == PieceType::K;
+                for (i, kind) in [mv.iscapture(), mv.isprom(), mv.isep(), mv.iscastle(), king].into_iter().enumerate() {
+                    seen[i] += kind as u32;
+                }
+                stack.push(&board, mv);
+                board.push(mv, &mg);
+                stack.refresh_king_move(&nnue, &board);
+                line.push(false);
+            }
+            // only some nodes are evaluated, so updates get replayed over several plies
+            if rng.random_range(0..3) == 0 {
+                let acc = stack.current(&nnue, &board);
+                let fresh = Accumulator::from_board(&nnue, &board);
+                assert!(acc.w == fresh.w && acc.b == fresh.b, "{}", board);
+            }
+        }
+    }
+    assert!(seen.iter().all(|&n| n >= 5), "{:?}", seen);
+}
+
+#[test]
+fn null_move_pruning_skips_pawn_endings() {
+    use crate::search::Search;
+
+    let mg = MoveGenerator::new();
+    let mut search = Search::new(false);
+    let mut board = Board::try_from_fen(PERFT_SUITE[1].0).unwrap();
+    search.search_iterative(&mut board, 5, &mg);
+    assert!(search.null_tries > 0);
+    assert_eq!(board.to_fen(), PERFT_SUITE[1].0);
+
+    // only kings and pawns: passing could be the best move, so it is never tried
+    let mut search = Search::new(false);
+    let mut board = Board::try_from_fen("8/8/8/1k6/3p4/3P4/4K3/8 w - - 0 1").unwrap();
+    search.search_iterative(&mut board, 8, &mg);
+    assert_eq!(search.null_tries, 0);
+}
+
+#[test]
+fn shallow_pruning_keeps_mates() {
+    use crate::search::search::MATE_WINDOW;
+    use crate::search::Search;
+
+    let mg = MoveGenerator::new();
+    let mut search = Search::new(false);
+    let mut board = Board::try_from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4").unwrap();
+    let (best, score) = search.search_iterative(&mut board, 5, &mg);
+    assert_eq!(best.to_uci(), "h5f7");
+    assert!(score > MATE_WINDOW);
+
+    let mut search = Search::new(false);
+    let mut board = Board::try_from_fen(PERFT_SUITE[1].0).unwrap();
+    search.search_iterative(&mut board, 5, &mg);
+    assert!(search.rfp_cutoffs > 0 && search.futility_prunes > 0);
+}
+
+#[test]
+fn search_reports_legal_pv() {
+    use crate::search::Search;
+
+    let mg = MoveGenerator::new();
+    // the mates need their whole line; a mate in one has nothing after the mating move
+    for (fen, min_len) in [
+        (PERFT_SUITE[0].0, 4),
+        (PERFT_SUITE[1].0, 4),
+        ("r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 10", 3),
+        ("r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4", 1),
+    ] {
+        let mut search = Search::new(false);
+        let mut board = Board::try_from_fen(fen).unwrap();
+        let (best, _) = search.search_iterative(&mut board, 6, &mg);
+        assert_eq!(search.pv.first(), Some(&best));
+        assert!(search.pv.len() >= min_len, "{}", Search::pv_to_uci(&search.pv, false));
+        // every move of the line is legal in the position it is played from
+        for &m in &search.pv {
+            assert!(mg.generate(&mut board).contains(&m), "{} not legal in {}", m.to_uci(), board.to_fen());
+            board.push(m, &mg);
+        }
+        for _ in &search.pv {
+            board.pop();
+        }
+        assert_eq!(board.to_fen(), fen);
+    }
+}
@@ -227,5 +227,25 @@ pub const Z_CASTLING: [u64; 16] = [
     0xF628F80164639D12, 0xC520A6D2753BD70F, 0xFC5251CD4968CAE9, 0x9C4D05793B1CE155,
 ];
 
+/// Extra castling key per rook home square, used for Chess960 rights whose rook is not on a corner.
+pub const Z_CASTLING_ROOK: [u64; 64] = [
+    0x8D26015663655B6F, 0xE7662D7E2C73B9CA, 0x0B7879E1E3A2A932, 0xEA9993144AAD683D,
+    0xC614BD9BD8379584, 0x86A98CEED27C11CC, 0x612FDADBB731D917, 0x4F23408BC2F7DE7F,
+    0x7B08DC257A82146E, 0xF6909893F4D20113, 0xF6FDA4FA8F36F3AE, 0x596BFA8A4FC2AB16,
+    0xDCFF799D9BE68089, 0xB3FC74B610EB0B49, 0x2AF53073406B561E, 0xE1C954B53AE6F18D,
+    0x344C8C1A42BFD29F, 0xBDD96F0B63B07675, 0xEE1F6E327B4B4DF5, 0xA13E3E65353E80AC,
+    0xE74D9F70E594465D, 0x346E4EB82749A433, 0x982C955B254C4742, 0x3EABCFB833805C1C,
+    0xA1F4373F7874924B, 0x7FA37179C5E243C8, 0x91F331434E7756E7, 0x02BBE98DCF7A28AD,
+    0x053078508D21D2D0, 0x2B40BD3D1C231CE6, 0xB9834E77378F6158, 0x9F17435840CC0B12,
+    0x79E6A790AEA76DE4, 0xD4F6A89951CDEFB5, 0xA56D47C284899FFD, 0x929208BB61DBB1D7,
+    0xCB4A2DE59C1A716E, 0x4307793E3566CB73, 0x3D09D56D58ADF1DF, 0x44E0E81E7CD4112C,
+    0xB896508D29DFFEDC, 0x3D2A8F9EC7EB1E17, 0xEACF1F107D556D94, 0x2B0452B564AEF158,
+    0x1A256339ACEE6B99, 0xFC9FE503A15D08FD, 0x8D03444C17652277, 0xA7A188267016AA1E,
+    0x46C92BEC2E83023D, 0xEAAD9BCE96190D0F, 0x666D4351509FE6FD, 0x1AD524C08AB04038,
+    0x8493BF278417E569, 0xFE93286237FE7BEB, 0x64C5D963ED420C6D, 0xEE03E775E7CCF3C5,
+    0xBC3723F5327653D0, 0x06D101B479820A43, 0xA0A34889FA4A9F33, 0xC66C3E6043C139CA,
+    0x7C73A3D1664D03FC, 0xE666166397AB8532, 0xD693A574CB027FAA, 0x3A379F7AD2C3C0C0,
+];
+
 pub const Z_EP_FILE: [u64; 8] = [
 0xB91D3E6447A9C56E, 0x38DA81CFE201D67D, 0xCDD6128FC0FBB0D8, 0x4958D2E802AE7606, 0xA6B5B497BA61ED8A, 0x98772ED467BD8E58, 0x3DAF992FBF6A86F1, 0xD8D1F079F6C7B5FF, ];
\ No newline at end of file
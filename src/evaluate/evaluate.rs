@@ -56,20 +56,20 @@ fn pst_value(table: &[i16; 64], sq: usize, color: usize) -> i32 {
         table[constlib::mirror_sq(sq)] as i32
     }
 }
-use crate::evaluate::Nnue;
+use crate::evaluate::nnue::{Accumulator, Nnue};
 
 #[inline(always)]
-pub fn evaluate_neural(board: &Board, nnue: &Nnue) -> i32 {
+pub fn evaluate_neural(acc: &Accumulator, turn: u8, nnue: &Nnue) -> i32 {
     let start = Instant::now();
-    let score = nnue.eval_cp_like(board);
+    let score = nnue.eval_acc(acc, turn);
     perf::record_eval_neural(start.elapsed());
     score
 }
 
 #[inline(always)]
-pub fn evaluate_neural_fast(board: &Board, nnue: &Nnue) -> i32 {
+pub fn evaluate_neural_fast(acc: &Accumulator, turn: u8, nnue: &Nnue) -> i32 {
     let start = Instant::now();
-    let score = nnue.eval_fast_cp_like(board);
+    let score = nnue.eval_acc_fast(acc, turn);
     perf::record_eval_neural(start.elapsed());
     score
 }
@@ -1,8 +1,10 @@
-use crate::core::{Board, PieceIndex};
+use crate::core::{constlib, Board, Move, Piece, PieceIndex, PieceType};
+use crate::perf;
 use std::convert::TryInto;
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
+use std::time::Instant;
 
 #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 use std::arch::is_x86_feature_detected;
@@ -14,8 +16,14 @@ use std::arch::x86_64::*;
 #[repr(align(32))]
 struct AlignedI16x512([i16; 512]);
 
+/// Where the engine looks for a network when none is given explicitly.
+pub const DEFAULT_NNUE_PATH: &str = "data/processed/nnue.bin";
+
 const MAGIC: &[u8; 4] = b"NNUE";
 const VERSION: u32 = 2;
+/// HalfKP network: one accumulator per king, fed by every (king square, piece, square)
+/// feature. Black's features are mirrored (see `side_feat_index`), so a position and its
+/// color-flipped twin score the same.
 #[derive(Clone)]
 pub struct Nnue {
     pub num_feat: usize,
@@ -254,18 +262,25 @@ impl Nnue {
         cp as i32
     }
 
+    /// Full-network score of `board`, built from scratch. Meant for one-off evaluations;
+    /// the search keeps accumulators incrementally and calls `eval_acc`.
+    pub fn eval_cp_like(&self, board: &Board) -> i32 {
+        self.eval_acc(&Accumulator::from_board(self, board), board.turn)
+    }
+
+    /// `eval_cp_like` through the fast head.
     pub fn eval_fast_cp_like(&self, board: &Board) -> i32 {
+        self.eval_acc_fast(&Accumulator::from_board(self, board), board.turn)
+    }
+
+    pub fn eval_acc_fast(&self, acc: &Accumulator, turn: u8) -> i32 {
         if !self.has_fast_head() {
-            return self.eval_cp_like(board);
+            return self.eval_acc(acc, turn);
         }
 
-        debug_assert!(board.nnue_inited);
+        debug_assert!(acc.computed);
 
-        let (stm, nstm) = if board.turn == 0 {
-            (&board.nnue_acc_w, &board.nnue_acc_b)
-        } else {
-            (&board.nnue_acc_b, &board.nnue_acc_w)
-        };
+        let (stm, nstm) = if turn == 0 { (&acc.w, &acc.b) } else { (&acc.b, &acc.w) };
 
         let clamp_hi = 127 * self.scale_emb;
         #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
@@ -292,14 +307,11 @@ impl Nnue {
         };
         cp as i32
     }
-    pub fn eval_cp_like(&self, board: &Board) -> i32 {
-        debug_assert!(board.nnue_inited);
+    /// Scores the position described by `acc` for the side `turn`.
+    pub fn eval_acc(&self, acc: &Accumulator, turn: u8) -> i32 {
+        debug_assert!(acc.computed);
 
-        let (stm, nstm) = if board.turn == 0 {
-            (&board.nnue_acc_w, &board.nnue_acc_b)
-        } else {
-            (&board.nnue_acc_b, &board.nnue_acc_w)
-        };
+        let (stm, nstm) = if turn == 0 { (&acc.w, &acc.b) } else { (&acc.b, &acc.w) };
 
         let clamp_hi = 127 * self.scale_emb;
         #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
@@ -438,13 +450,18 @@ fn feat_index(king_sq: usize, piece_idx: usize, piece_sq: usize) -> usize {
     ((king_sq * 12 + piece_idx) * 64 + piece_sq) + 1 // +1 for PAD row
 }
 
+/// Feature index in `color`'s accumulator. Black looks at the board from its own side:
+/// ranks flipped and colors swapped, so "own pawn on the 2nd rank" is the same feature
+/// for both sides.
 #[inline(always)]
-fn apply_delta(acc: &mut [i32; 256], row: &[i16], sign: i32) {
-    // sign is +1 (add) or -1 (remove)
-    for i in 0..256 {
-        acc[i] += sign * (row[i] as i32);
+fn side_feat_index(color: usize, king_sq: usize, piece_idx: usize, piece_sq: usize) -> usize {
+    if color == 0 {
+        feat_index(king_sq, piece_idx, piece_sq)
+    } else {
+        feat_index(king_sq ^ 56, (piece_idx + 6) % 12, piece_sq ^ 56)
     }
 }
+
 #[inline(always)]
 pub fn add_row(acc: &mut [i32; 256], row: &[i16]) {
     for i in 0..256 {
@@ -469,8 +486,8 @@ pub fn nnue_add_piece(
     piece_idx: usize,
     sq: usize,
 ) {
-    let fw = feat_index(wk_sq, piece_idx, sq);
-    let fb = feat_index(bk_sq, piece_idx, sq);
+    let fw = side_feat_index(0, wk_sq, piece_idx, sq);
+    let fb = side_feat_index(1, bk_sq, piece_idx, sq);
     add_row(acc_w, nnue.emb_row(fw));
     add_row(acc_b, nnue.emb_row(fb));
 }
@@ -485,29 +502,11 @@ pub fn nnue_sub_piece(
     piece_idx: usize,
     sq: usize,
 ) {
-    let fw = feat_index(wk_sq, piece_idx, sq);
-    let fb = feat_index(bk_sq, piece_idx, sq);
+    let fw = side_feat_index(0, wk_sq, piece_idx, sq);
+    let fb = side_feat_index(1, bk_sq, piece_idx, sq);
     sub_row(acc_w, nnue.emb_row(fw));
     sub_row(acc_b, nnue.emb_row(fb));
 }
-#[inline(always)]
-fn update_piece_move(nnue: &Nnue, board: &mut Board, piece_idx: usize, from: usize, to: usize) {
-    let wk_sq = board.pieces[PieceIndex::K.index()].trailing_zeros() as usize;
-    let bk_sq = board.pieces[6 + PieceIndex::K.index()].trailing_zeros() as usize;
-
-    // Update white-king perspective accumulator
-    let f_w = feat_index(wk_sq, piece_idx, from);
-    let t_w = feat_index(wk_sq, piece_idx, to);
-    apply_delta(&mut board.nnue_acc_w, nnue.emb_row(f_w), -1);
-    apply_delta(&mut board.nnue_acc_w, nnue.emb_row(t_w), 1);
-
-    // Update black-king perspective accumulator
-    let f_b = feat_index(bk_sq, piece_idx, from);
-    let t_b = feat_index(bk_sq, piece_idx, to);
-    apply_delta(&mut board.nnue_acc_b, nnue.emb_row(f_b), -1);
-    apply_delta(&mut board.nnue_acc_b, nnue.emb_row(t_b), 1);
-}
-
 #[inline(always)]
 pub fn nnue_move_piece(
     nnue: &Nnue,
@@ -519,10 +518,10 @@ pub fn nnue_move_piece(
     from: usize,
     to: usize,
 ) {
-    let fw_from = feat_index(wk_sq, piece_idx, from);
-    let fw_to = feat_index(wk_sq, piece_idx, to);
-    let fb_from = feat_index(bk_sq, piece_idx, from);
-    let fb_to = feat_index(bk_sq, piece_idx, to);
+    let fw_from = side_feat_index(0, wk_sq, piece_idx, from);
+    let fw_to = side_feat_index(0, wk_sq, piece_idx, to);
+    let fb_from = side_feat_index(1, bk_sq, piece_idx, from);
+    let fb_to = side_feat_index(1, bk_sq, piece_idx, to);
 
     let w_from = nnue.emb_row(fw_from);
     let w_to = nnue.emb_row(fw_to);
@@ -534,3 +533,242 @@ pub fn nnue_move_piece(
         acc_b[i] += (b_to[i] as i32) - (b_from[i] as i32);
     }
 }
+
+// ---- accumulator stack ----
+// The board knows nothing about the net. The search keeps one `Accumulator` per ply and a
+// move only records which pieces it touched; the sums are brought up to date when a node
+// is actually evaluated, so nodes cut off before their evaluation never pay for it.
+
+/// `piece` leaves `from` and/or arrives on `to` (64 = none).
+#[derive(Clone, Copy, Default)]
+struct DirtyPiece {
+    piece: u8,
+    from: u8,
+    to: u8,
+}
+
+#[derive(Clone)]
+pub struct Accumulator {
+    pub w: [i32; 256],
+    pub b: [i32; 256],
+    /// Pieces changed by the move into this ply: mover, captured piece, promoted piece.
+    dirty: [DirtyPiece; 3],
+    n_dirty: usize,
+    /// A king moved. HalfKP features hang off the king square, so the parent is no use.
+    needs_refresh: bool,
+    /// `w` and `b` are up to date.
+    pub computed: bool,
+}
+
+impl Accumulator {
+    fn new() -> Self {
+        Self {
+            w: [0; 256],
+            b: [0; 256],
+            dirty: [DirtyPiece::default(); 3],
+            n_dirty: 0,
+            needs_refresh: false,
+            computed: false,
+        }
+    }
+
+    pub fn from_board(nnue: &Nnue, board: &Board) -> Self {
+        let mut acc = Self::new();
+        acc.refresh(nnue, board);
+        acc
+    }
+
+    /// Recomputes both accumulators from scratch.
+    pub fn refresh(&mut self, nnue: &Nnue, board: &Board) {
+        debug_assert_eq!(nnue.b1.len(), 256);
+
+        // Start from bias
+        self.w.copy_from_slice(&nnue.b1);
+        self.b.copy_from_slice(&nnue.b1);
+
+        let (wk_sq, bk_sq) = king_squares(board);
+        for piece_idx in 0..12usize {
+            let mut bb = board.pieces[piece_idx];
+            while bb != 0 {
+                let sq = constlib::poplsb(&mut bb) as usize;
+                nnue_add_piece(nnue, &mut self.w, &mut self.b, wk_sq, bk_sq, piece_idx, sq);
+            }
+        }
+        self.computed = true;
+    }
+
+    #[inline(always)]
+    fn add_dirty(&mut self, piece: Piece, from: u8, to: u8) {
+        self.dirty[self.n_dirty] = DirtyPiece { piece: piece.getidx() as u8, from, to };
+        self.n_dirty += 1;
+    }
+
+    /// `parent` plus this ply's dirty pieces, in one pass. Kings have not moved since `parent`.
+    fn update_from(&mut self, parent: &Accumulator, nnue: &Nnue, wk_sq: usize, bk_sq: usize) {
+        // a normal move adds one feature and removes one or two (mover, captured piece)
+        let mut add = None;
+        let mut sub = [None; 2];
+        let mut n_sub = 0;
+        for d in &self.dirty[..self.n_dirty] {
+            let piece = d.piece as usize;
+            if d.to != 64 {
+                debug_assert!(add.is_none());
+                add = Some((piece, d.to as usize));
+            }
+            if d.from != 64 {
+                sub[n_sub] = Some((piece, d.from as usize));
+                n_sub += 1;
+            }
+        }
+
+        for (color, acc, prev, k_sq) in [(0, &mut self.w, &parent.w, wk_sq), (1, &mut self.b, &parent.b, bk_sq)] {
+            let row =
+                |f: Option<(usize, usize)>| f.map(|(piece, sq)| nnue.emb_row(side_feat_index(color, k_sq, piece, sq)));
+            match (row(add), row(sub[0]), row(sub[1])) {
+                (Some(a), Some(s0), None) => {
+                    for i in 0..256 {
+                        acc[i] = prev[i] + a[i] as i32 - s0[i] as i32;
+                    }
+                }
+                (Some(a), Some(s0), Some(s1)) => {
+                    for i in 0..256 {
+                        acc[i] = prev[i] + a[i] as i32 - s0[i] as i32 - s1[i] as i32;
+                    }
+                }
+                (a, s0, s1) => {
+                    *acc = *prev;
+                    if let Some(a) = a {
+                        add_row(acc, a);
+                    }
+                    for s in [s0, s1].into_iter().flatten() {
+                        sub_row(acc, s);
+                    }
+                }
+            }
+        }
+        self.computed = true;
+    }
+}
+
+#[inline(always)]
+fn king_squares(board: &Board) -> (usize, usize) {
+    (
+        board.pieces[PieceIndex::K.index()].trailing_zeros() as usize,
+        board.pieces[PieceIndex::k.index()].trailing_zeros() as usize,
+    )
+}
+
+/// Accumulators for every ply from the search root down to the current node.
+/// `push`/`pop` mirror `Board::push`/`Board::pop`.
+pub struct AccumulatorStack {
+    entries: Vec<Accumulator>,
+    len: usize,
+}
+
+impl AccumulatorStack {
+    pub fn new() -> Self {
+        Self { entries: Vec::new(), len: 0 }
+    }
+
+    /// Starts over with `board` as the root.
+    pub fn reset(&mut self, nnue: &Nnue, board: &Board) {
+        self.len = 0;
+        self.next_entry().refresh(nnue, board);
+    }
+
+    #[inline(always)]
+    fn next_entry(&mut self) -> &mut Accumulator {
+        if self.len == self.entries.len() {
+            self.entries.push(Accumulator::new());
+        }
+        self.len += 1;
+        &mut self.entries[self.len - 1]
+    }
+
+    /// Records the pieces `mv` changes. Call with the board as it is *before* `Board::push`.
+    pub fn push(&mut self, board: &Board, mv: Move) {
+        let timer = Instant::now();
+        let from = mv.getSrc();
+        let to = mv.getDst();
+        let moved = board.piecelocs.piece_at(from);
+
+        let e = self.next_entry();
+        e.computed = false;
+        e.n_dirty = 0;
+        e.needs_refresh = mv.iscastle() || moved.get_piece_type() == PieceType::K;
+        if !e.needs_refresh {
+            let color = moved.get_color();
+            if mv.isep() {
+                let sq = if color == 0 { to - 8 } else { to + 8 };
+                e.add_dirty(Piece::make(color ^ 1, PieceType::P), sq, 64);
+            } else if mv.iscapture() {
+                e.add_dirty(board.piecelocs.piece_at(to), to, 64);
+            }
+            if mv.isprom() {
+                e.add_dirty(moved, from, 64);
+                e.add_dirty(Piece::make(color, mv.prompiece()), 64, to);
+            } else {
+                e.add_dirty(moved, from, to);
+            }
+        }
+        perf::record_push_nn_update(timer.elapsed());
+    }
+
+    /// Mirrors `Board::push_null`: no piece changes, so the ply just copies its parent.
+    pub fn push_null(&mut self) {
+        let e = self.next_entry();
+        e.computed = false;
+        e.n_dirty = 0;
+        e.needs_refresh = false;
+    }
+
+    /// A king move cannot be replayed from its parent, and once the search has gone
+    /// deeper its position is gone too, so that ply is rebuilt right away.
+    /// Call straight after `Board::push`.
+    #[inline(always)]
+    pub fn refresh_king_move(&mut self, nnue: &Nnue, board: &Board) {
+        let e = &mut self.entries[self.len - 1];
+        if e.needs_refresh {
+            let timer = Instant::now();
+            e.refresh(nnue, board);
+            perf::record_acc_update(timer.elapsed());
+        }
+    }
+
+    #[inline(always)]
+    pub fn pop(&mut self) {
+        debug_assert!(self.len > 1, "AccumulatorStack::pop past the root");
+        self.len -= 1;
+    }
+
+    /// Accumulators for `board`, which must be the position at the top of the stack.
+    /// Replays dirty pieces forward from the nearest computed ply; if an unrefreshed
+    /// king move is on the way the top is rebuilt from `board` instead.
+    pub fn current(&mut self, nnue: &Nnue, board: &Board) -> &Accumulator {
+        let top = self.len - 1;
+        if !self.entries[top].computed {
+            let timer = Instant::now();
+            let mut base = top;
+            while !self.entries[base].computed && !self.entries[base].needs_refresh {
+                base -= 1;
+            }
+            if self.entries[base].computed {
+                let (wk_sq, bk_sq) = king_squares(board);
+                for i in base + 1..=top {
+                    let (done, rest) = self.entries.split_at_mut(i);
+                    rest[0].update_from(&done[i - 1], nnue, wk_sq, bk_sq);
+                }
+            } else {
+                self.entries[top].refresh(nnue, board);
+            }
+            perf::record_acc_update(timer.elapsed());
+        }
+        &self.entries[top]
+    }
+}
+
+impl Default for AccumulatorStack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,5 +1,6 @@
 pub mod core;
 pub mod uci;
+pub mod pgn;
 
 pub mod search;
 pub mod evaluate;
@@ -2,7 +2,6 @@
 #[allow(nonstandard_style)]
 
 use rustychess::core::{cli, constlib};
-use rustychess::search::Search;
 use rustychess::core::{Board, movegen, Move};
 
 use std::env;
@@ -62,10 +61,9 @@ fn main() {
     
     let mg = movegen::MoveGenerator::new();
     use std::time::Instant;
-    let mut search = Search::new(false);
-    board.from_fen(String::from("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), &search.nnue);
+    board.from_fen(String::from("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
 
-    cli::interactive_cli(&mut board, &mg, &search.nnue);
+    cli::interactive_cli(&mut board, &mg);
 
 }
 
@@ -36,12 +36,16 @@ static PUSH_NN_COUNT: AtomicU64 = AtomicU64::new(0);
 static POP_TIME: AtomicU64 = AtomicU64::new(0);
 static POP_COUNT: AtomicU64 = AtomicU64::new(0);
 
+static ACC_UPDATE_TIME: AtomicU64 = AtomicU64::new(0);
+static ACC_UPDATE_COUNT: AtomicU64 = AtomicU64::new(0);
+
 #[derive(Debug, Default, Clone, Copy)]
 pub struct PerfSnapshot {
     pub eval_neural: TimerStat,
     pub push: TimerStat,
     pub push_nn: TimerStat,
     pub pop: TimerStat,
+    pub acc_update: TimerStat,
 }
 
 fn record(timer: (&AtomicU64, &AtomicU64), d: Duration) {
@@ -66,6 +70,10 @@ pub fn record_pop(d: Duration) {
     record((&POP_TIME, &POP_COUNT), d);
 }
 
+pub fn record_acc_update(d: Duration) {
+    record((&ACC_UPDATE_TIME, &ACC_UPDATE_COUNT), d);
+}
+
 pub fn reset() {
     for a in [
         &EVAL_NEURAL_TIME,
@@ -76,6 +84,8 @@ pub fn reset() {
         &PUSH_NN_COUNT,
         &POP_TIME,
         &POP_COUNT,
+        &ACC_UPDATE_TIME,
+        &ACC_UPDATE_COUNT,
     ] {
         a.store(0, Ordering::Relaxed);
     }
@@ -99,6 +109,10 @@ pub fn snapshot() -> PerfSnapshot {
             total_ns: POP_TIME.load(Ordering::Relaxed),
             count: POP_COUNT.load(Ordering::Relaxed),
         },
+        acc_update: TimerStat {
+            total_ns: ACC_UPDATE_TIME.load(Ordering::Relaxed),
+            count: ACC_UPDATE_COUNT.load(Ordering::Relaxed),
+        },
     }
 }
 
@@ -128,4 +142,10 @@ pub fn print_snapshot(label: &str, snapshot: PerfSnapshot) {
         snapshot.pop.count,
         snapshot.pop.avg_us()
     );
+    println!(
+        "  acc update   total={:.3} ms  count={}  avg={:.3} us",
+        snapshot.acc_update.total_ms(),
+        snapshot.acc_update.count,
+        snapshot.acc_update.avg_us()
+    );
 }
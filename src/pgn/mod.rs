@@ -0,0 +1,3 @@
+pub mod pgn;
+
+pub use pgn::{parse_pgn, write_pgn, Game, GameResult, MoveNode, PgnError};
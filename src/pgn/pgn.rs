@@ -0,0 +1,555 @@
+use std::fmt;
+
+use crate::core::movegen::MoveGenerator;
+use crate::core::{Board, FenError, Move, SanError};
+
+const STARTPOS: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+/// Export format keeps movetext lines under 80 columns.
+const LINE_WIDTH: usize = 79;
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Unknown,
+}
+
+impl GameResult {
+    pub fn parse(s: &str) -> Option<GameResult> {
+        match s {
+            "1-0" => Some(GameResult::WhiteWins),
+            "0-1" => Some(GameResult::BlackWins),
+            "1/2-1/2" => Some(GameResult::Draw),
+            "*" => Some(GameResult::Unknown),
+            _ => None,
+        }
+    }
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Unknown => "*",
+        }
+    }
+}
+
+/// One half-move of a game or variation, with its annotations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveNode {
+    pub mv: Move,
+    pub san: String,
+    /// Numeric annotation glyphs ($1 == "!", $2 == "?", ...).
+    pub nags: Vec<u8>,
+    /// Comment placed before the move (only at the start of a line).
+    pub comment_before: Option<String>,
+    pub comment: Option<String>,
+    /// Alternatives to this move, each starting from the position before it.
+    pub variations: Vec<Vec<MoveNode>>,
+}
+
+impl MoveNode {
+    pub fn new(mv: Move, san: String) -> Self {
+        Self { mv, san, nags: Vec::new(), comment_before: None, comment: None, variations: Vec::new() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Game {
+    /// Tag pairs in file order.
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<MoveNode>,
+    /// Comment with no move to belong to, e.g. the only content of an empty movetext.
+    pub comment: Option<String>,
+    pub result: GameResult,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PgnError {
+    Syntax { line: usize, msg: String },
+    Move { line: usize, err: SanError },
+    Fen { line: usize, err: FenError },
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnError::Syntax { line, msg } => write!(f, "line {}: {}", line, msg),
+            PgnError::Move { line, err } => write!(f, "line {}: {}", line, err),
+            PgnError::Fen { line, err } => write!(f, "line {}: bad FEN tag: {}", line, err),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+impl Game {
+    /// Empty game with the seven tag roster filled in with "?".
+    pub fn new() -> Self {
+        let mut tags: Vec<(String, String)> =
+            SEVEN_TAG_ROSTER.iter().map(|t| (t.to_string(), String::from("?"))).collect();
+        tags[6].1 = String::from("*");
+        Self { tags, moves: Vec::new(), comment: None, result: GameResult::Unknown }
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(k, _)| k == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn set_result(&mut self, result: GameResult) {
+        self.result = result;
+        self.set_tag("Result", result.as_str());
+    }
+
+    pub fn start_fen(&self) -> &str {
+        self.tag("FEN").unwrap_or(STARTPOS)
+    }
+
+    /// Position the movetext starts from.
+    pub fn start_board(&self) -> Result<Board, FenError> {
+        Board::try_from_fen(self.start_fen())
+    }
+
+    pub fn mainline(&self) -> impl Iterator<Item = Move> + '_ {
+        self.moves.iter().map(|n| n.mv)
+    }
+
+    /// Final position of the main line.
+    pub fn replay(&self, mg: &MoveGenerator) -> Result<Board, FenError> {
+        let mut board = self.start_board()?;
+        for mv in self.mainline() {
+            board.push(mv, mg);
+        }
+        Ok(board)
+    }
+
+    /// Appends `mv` to the main line and plays it on `board`, which must be
+    /// the current final position of the game.
+    pub fn push_move(&mut self, board: &mut Board, mv: Move, mg: &MoveGenerator) {
+        let san = board.move_to_san(mv, mg);
+        board.push(mv, mg);
+        self.moves.push(MoveNode::new(mv, san));
+    }
+
+    pub fn to_pgn(&self) -> String {
+        let mut out = String::new();
+        for (name, value) in &self.tags {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            out.push_str(&format!("[{} \"{}\"]\n", name, value));
+        }
+        out.push('\n');
+
+        // absolute ply of the first move, so move numbers follow the FEN
+        let start_ply = match self.start_board() {
+            Ok(b) => (b.fullmove_number.max(1) as usize - 1) * 2 + b.turn as usize,
+            Err(_) => 0,
+        };
+        let mut words = Vec::new();
+        write_line(&self.moves, start_ply, &mut words);
+        if let Some(c) = &self.comment {
+            push_comment(&mut words, c);
+        }
+        words.push(self.result.as_str().to_string());
+
+        let mut line_len = 0;
+        for w in words {
+            if line_len > 0 && line_len + 1 + w.len() > LINE_WIDTH {
+                out.push('\n');
+                line_len = 0;
+            }
+            if line_len > 0 {
+                out.push(' ');
+                line_len += 1;
+            }
+            out.push_str(&w);
+            line_len += w.len();
+        }
+        out.push_str("\n\n");
+        out
+    }
+}
+
+// Comments are split into words so the wrapper can break inside them.
+fn push_comment(words: &mut Vec<String>, comment: &str) {
+    let mut parts: Vec<String> = comment.split_whitespace().map(String::from).collect();
+    if parts.is_empty() {
+        words.push(String::from("{}"));
+        return;
+    }
+    parts[0].insert(0, '{');
+    parts.last_mut().unwrap().push('}');
+    words.extend(parts);
+}
+
+fn write_line(nodes: &[MoveNode], start_ply: usize, words: &mut Vec<String>) {
+    let mut need_number = true;
+    for (i, node) in nodes.iter().enumerate() {
+        let ply = start_ply + i;
+        if let Some(c) = &node.comment_before {
+            push_comment(words, c);
+            need_number = true;
+        }
+        if ply.is_multiple_of(2) {
+            words.push(format!("{}.", ply / 2 + 1));
+        } else if need_number {
+            words.push(format!("{}...", ply / 2 + 1));
+        }
+        words.push(node.san.clone());
+        need_number = false;
+
+        for nag in &node.nags {
+            words.push(format!("${}", nag));
+        }
+        if let Some(c) = &node.comment {
+            push_comment(words, c);
+            need_number = true;
+        }
+        for var in &node.variations {
+            let mut sub = Vec::new();
+            write_line(var, ply, &mut sub);
+            match sub.len() {
+                0 => words.push(String::from("()")),
+                _ => {
+                    sub[0].insert(0, '(');
+                    sub.last_mut().unwrap().push(')');
+                    words.extend(sub);
+                }
+            }
+            need_number = true;
+        }
+    }
+}
+
+pub fn write_pgn(games: &[Game]) -> String {
+    games.iter().map(Game::to_pgn).collect()
+}
+
+// ================= reading =================
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    Open,
+    Close,
+    Result(GameResult),
+    San(String),
+}
+
+fn glyph_nag(s: &str) -> Option<u8> {
+    match s {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, PgnError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let mut line = 1;
+    let mut line_start = true;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\n' {
+            line += 1;
+            line_start = true;
+            i += 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        // '%' in the first column escapes the rest of the line
+        if c == '%' && line_start {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+        line_start = false;
+        let tok_line = line;
+
+        match c {
+            '[' => {
+                i += 1;
+                let name_start = i;
+                while i < chars.len() && !chars[i].is_whitespace() && chars[i] != '"' && chars[i] != ']' {
+                    i += 1;
+                }
+                let name: String = chars[name_start..i].iter().collect();
+                while i < chars.len() && chars[i].is_whitespace() && chars[i] != '\n' {
+                    i += 1;
+                }
+                if name.is_empty() || chars.get(i) != Some(&'"') {
+                    return Err(PgnError::Syntax { line, msg: String::from("malformed tag pair") });
+                }
+                i += 1;
+                let mut value = String::new();
+                loop {
+                    match chars.get(i) {
+                        Some('\\') if matches!(chars.get(i + 1), Some('"') | Some('\\')) => {
+                            value.push(chars[i + 1]);
+                            i += 2;
+                        }
+                        Some('"') => {
+                            i += 1;
+                            break;
+                        }
+                        Some('\n') | None => {
+                            return Err(PgnError::Syntax { line, msg: String::from("unterminated tag value") })
+                        }
+                        Some(&ch) => {
+                            value.push(ch);
+                            i += 1;
+                        }
+                    }
+                }
+                while i < chars.len() && chars[i] != ']' && chars[i] != '\n' {
+                    i += 1;
+                }
+                if chars.get(i) != Some(&']') {
+                    return Err(PgnError::Syntax { line, msg: String::from("unterminated tag pair") });
+                }
+                i += 1;
+                tokens.push((Token::Tag(name, value), tok_line));
+            }
+            '{' => {
+                i += 1;
+                let start = i;
+                while i < chars.len() && chars[i] != '}' {
+                    if chars[i] == '\n' {
+                        line += 1;
+                    }
+                    i += 1;
+                }
+                if i == chars.len() {
+                    return Err(PgnError::Syntax { line: tok_line, msg: String::from("unterminated comment") });
+                }
+                let text: String = chars[start..i].iter().collect();
+                tokens.push((Token::Comment(text.split_whitespace().collect::<Vec<_>>().join(" ")), tok_line));
+                i += 1;
+            }
+            ';' => {
+                i += 1;
+                let start = i;
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                tokens.push((Token::Comment(text.trim().to_string()), tok_line));
+            }
+            '(' => {
+                tokens.push((Token::Open, tok_line));
+                i += 1;
+            }
+            ')' => {
+                tokens.push((Token::Close, tok_line));
+                i += 1;
+            }
+            '$' => {
+                i += 1;
+                let start = i;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+                let digits: String = chars[start..i].iter().collect();
+                let nag = digits
+                    .parse::<u8>()
+                    .map_err(|_| PgnError::Syntax { line, msg: format!("bad NAG ${}", digits) })?;
+                tokens.push((Token::Nag(nag), tok_line));
+            }
+            _ => {
+                let start = i;
+                while i < chars.len()
+                    && !chars[i].is_whitespace()
+                    && !matches!(chars[i], '{' | '}' | '(' | ')' | '[' | ']' | ';' | '$')
+                {
+                    i += 1;
+                }
+                let symbol: String = chars[start..i].iter().collect();
+                if symbol.is_empty() {
+                    return Err(PgnError::Syntax { line, msg: format!("unexpected character '{}'", c) });
+                }
+                if let Some(result) = GameResult::parse(&symbol) {
+                    tokens.push((Token::Result(result), tok_line));
+                    continue;
+                }
+                // strip a move number prefix ("12." / "12..." / "12...Nf3")
+                let rest = symbol.trim_start_matches(|ch: char| ch.is_ascii_digit());
+                let rest = if rest.len() < symbol.len() && rest.starts_with('.') {
+                    rest.trim_start_matches('.')
+                } else {
+                    symbol.trim_start_matches('.')
+                };
+                if rest.is_empty() {
+                    continue;
+                }
+                // trailing "!", "?!" ... become NAGs
+                let san = rest.trim_end_matches(['!', '?']);
+                tokens.push((Token::San(san.to_string()), tok_line));
+                if san.len() < rest.len() {
+                    let glyph = &rest[san.len()..];
+                    match glyph_nag(glyph) {
+                        Some(nag) => tokens.push((Token::Nag(nag), tok_line)),
+                        None => {
+                            return Err(PgnError::Syntax { line, msg: format!("unknown annotation {}", glyph) })
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    mg: &'a MoveGenerator,
+}
+
+impl<'a> Parser<'a> {
+    fn line(&self) -> usize {
+        self.tokens.get(self.pos).or(self.tokens.last()).map_or(1, |t| t.1)
+    }
+
+    fn parse_game(&mut self) -> Result<Game, PgnError> {
+        let mut game = Game { tags: Vec::new(), moves: Vec::new(), comment: None, result: GameResult::Unknown };
+        while let Some((Token::Tag(name, value), _)) = self.tokens.get(self.pos) {
+            game.tags.push((name.clone(), value.clone()));
+            self.pos += 1;
+        }
+
+        let line = self.line();
+        let mut board = game.start_board().map_err(|err| PgnError::Fen { line, err })?;
+        (game.moves, game.comment) = self.parse_line(&mut board, 0)?;
+
+        if let Some((Token::Result(r), _)) = self.tokens.get(self.pos) {
+            game.result = *r;
+            self.pos += 1;
+        } else if let Some(r) = game.tag("Result").and_then(GameResult::parse) {
+            game.result = r;
+        }
+        Ok(game)
+    }
+
+    // Parses moves until the end of the current line (`)` for variations, a result,
+    // the next game's tags or EOF). `board` is restored before returning. A line with
+    // comments but no moves hands them back separately, for the caller to keep.
+    fn parse_line(&mut self, board: &mut Board, depth: usize) -> Result<(Vec<MoveNode>, Option<String>), PgnError> {
+        let mut nodes: Vec<MoveNode> = Vec::new();
+        let mut pending: Option<String> = None;
+
+        loop {
+            let Some((tok, line)) = self.tokens.get(self.pos).cloned() else {
+                if depth > 0 {
+                    return Err(PgnError::Syntax { line: self.line(), msg: String::from("unterminated variation") });
+                }
+                break;
+            };
+            match tok {
+                Token::San(s) => {
+                    let mv = board.parse_san(&s, self.mg).map_err(|err| PgnError::Move { line, err })?;
+                    let mut node = MoveNode::new(mv, board.move_to_san(mv, self.mg));
+                    node.comment_before = pending.take();
+                    board.push(mv, self.mg);
+                    nodes.push(node);
+                }
+                Token::Nag(n) => match nodes.last_mut() {
+                    Some(node) => node.nags.push(n),
+                    None => return Err(PgnError::Syntax { line, msg: String::from("NAG before any move") }),
+                },
+                Token::Comment(c) => {
+                    let slot = match nodes.last_mut() {
+                        Some(node) if pending.is_none() => &mut node.comment,
+                        _ => &mut pending,
+                    };
+                    match slot {
+                        Some(existing) => {
+                            existing.push(' ');
+                            existing.push_str(&c);
+                        }
+                        None => *slot = Some(c),
+                    }
+                }
+                Token::Open => {
+                    let Some(last) = nodes.last().map(|n| n.mv) else {
+                        return Err(PgnError::Syntax { line, msg: String::from("variation before any move") });
+                    };
+                    self.pos += 1;
+                    board.pop();
+                    let (var, comment) = self.parse_line(board, depth + 1)?;
+                    board.push(last, self.mg);
+                    let parent = nodes.last_mut().unwrap();
+                    // an empty variation's comment goes to the move it was an alternative to
+                    if let Some(c) = comment {
+                        match &mut parent.comment {
+                            Some(existing) => {
+                                existing.push(' ');
+                                existing.push_str(&c);
+                            }
+                            None => parent.comment = Some(c),
+                        }
+                    }
+                    parent.variations.push(var);
+                    continue;
+                }
+                Token::Close => {
+                    if depth == 0 {
+                        return Err(PgnError::Syntax { line, msg: String::from("unmatched ')'") });
+                    }
+                    self.pos += 1;
+                    break;
+                }
+                Token::Result(_) | Token::Tag(..) => {
+                    if depth > 0 {
+                        return Err(PgnError::Syntax { line, msg: String::from("unterminated variation") });
+                    }
+                    break;
+                }
+            }
+            self.pos += 1;
+        }
+
+        // comments after the last move already went to it, so anything pending means
+        // there were no moves at all
+        for _ in 0..nodes.len() {
+            board.pop();
+        }
+        Ok((nodes, pending))
+    }
+}
+
+/// Parses every game in a (possibly multi-game) PGN text, checking each move for legality.
+pub fn parse_pgn(text: &str, mg: &MoveGenerator) -> Result<Vec<Game>, PgnError> {
+    let mut parser = Parser { tokens: tokenize(text)?, pos: 0, mg };
+    let mut games = Vec::new();
+    while parser.pos < parser.tokens.len() {
+        games.push(parser.parse_game()?);
+    }
+    Ok(games)
+}
@@ -1,10 +1,10 @@
+use crate::core::check::CheckInfo;
 use crate::core::{constlib, movegen::MoveGenerator, Board, Move, PieceIndex};
 use crate::search::tt::{TT_EMPTY, TT_EXACT, TT_LOWER, TT_UPPER};
+use crate::search::movepick::MovePicker;
+use crate::search::search::{MATE, MATE_WINDOW};
 use crate::search::Search;
 
-const Mate: i32 = 99999;
-
-const MATE_WINDOW: i32 = 90000; // centipawns
 pub fn alphabeta(
     search: &mut Search,
     board: &mut Board,
@@ -15,6 +15,17 @@ pub fn alphabeta(
 ) -> i32 {
     //update search
     search.nodes += 1;
+    if search.should_stop() {
+        return 0;
+    }
+    let sply = search.search_ply(board);
+    search.clear_pv(sply);
+    // set by the parent when the path so far is the previous iteration's PV
+    let pv_move = search.take_pv_move(sply);
+    // alphabeta is never called on the root, so any repetition here is a real draw claim
+    if board.is_repetition() || board.is_insufficient_material() {
+        return search.draw_score(board);
+    }
     let key = board.hash;
     let ply = board.ply;
     let alpha0 = alpha;
@@ -56,15 +67,81 @@ pub fn alphabeta(
         return qsearch(search, board, generator, alpha, beta, 0);
     }
 
-    let mut moves = generator.generate(board);
     let in_check = generator.in_check(board);
+    if board.is_fifty_move_draw() {
+        // checkmate on the hundredth ply still wins
+        if in_check && generator.generate(board).is_empty() {
+            return -MATE + board.ply as i32;
+        }
+        return search.draw_score(board);
+    }
 
-    if moves.is_empty() {
-        // check for checkmate or stalemate
-        if in_check {
-            return -99999 + board.ply as i32; // checkmate score
-        } else {
-            return 0; // stalemate
+    // The static-eval prunings below only run at non-PV nodes out of check, and never
+    // when a bound is a mate score.
+    let margins = search.margins;
+    let static_eval = if !in_check && beta - alpha == 1 && beta.abs() < MATE_WINDOW {
+        Some(search.eval(board, generator))
+    } else {
+        None
+    };
+
+    if let Some(static_eval) = static_eval {
+        // --- Reverse futility: so far above beta that no quiet reply will bring it back ---
+        if depth <= RFP_MAX_DEPTH && static_eval - margins.rfp * depth as i32 >= beta {
+            search.rfp_cutoffs += 1;
+            return static_eval;
+        }
+        // --- Razoring: so far below alpha that only captures could help ---
+        if depth <= RAZOR_MAX_DEPTH && static_eval + margins.razor * (depth as i32) < alpha {
+            let v = qsearch(search, board, generator, alpha, beta, 0);
+            if search.stopped() {
+                return 0;
+            }
+            if v <= alpha {
+                search.razor_cutoffs += 1;
+                return v;
+            }
+        }
+    }
+
+    // --- Null-move pruning: if passing still fails high, some real move will too ---
+    // Never twice in a row, and never with just pawns left, where zugzwang makes passing
+    // better than any move.
+    if let Some(static_eval) = static_eval.filter(|_| {
+        depth >= NULL_MIN_DEPTH && !search.null_verifying && !after_null_move(board) && has_non_pawn_material(board)
+    }) {
+        if static_eval >= beta {
+            // reduce more at depth and when far above beta
+            let r = 3 + depth / 4 + ((static_eval - beta) / 200).min(2) as u8;
+            search.null_tries += 1;
+            search.make_null_move(board);
+            let score = -alphabeta(search, board, depth.saturating_sub(1 + r), generator, -beta, -beta + 1);
+            search.unmake_null_move(board);
+            if search.stopped() {
+                return 0;
+            }
+            if score >= beta {
+                // a mate found after passing is not a proven mate
+                let score = if score > MATE_WINDOW { beta } else { score };
+                if depth < NULL_VERIFY_DEPTH {
+                    search.null_cutoffs += 1;
+                    return score;
+                }
+                // deep cutoffs are checked by a reduced search of our own moves
+                search.null_verifications += 1;
+                search.null_verifying = true;
+                let v = alphabeta(search, board, depth.saturating_sub(r), generator, beta - 1, beta);
+                search.null_verifying = false;
+                // it searched this same node; drop the line it left behind
+                search.clear_pv(sply);
+                if search.stopped() {
+                    return 0;
+                }
+                if v >= beta {
+                    search.null_cutoffs += 1;
+                    return score;
+                }
+            }
         }
     }
 
@@ -73,25 +150,29 @@ pub fn alphabeta(
     if in_check && depth < 15 {
         depth += 1; // check extension
     }
-    //get the transposition table move regardless of depth and put it first.
-    let tt_move = if entry.flag != TT_EMPTY {
-        entry.best
+    //the TT move is tried first; the picker checks it is legal here before handing it out.
+    let tt_move = if entry.flag != TT_EMPTY && entry.key == key {
+        Move::from_u16(entry.best)
     } else {
-        0
+        Move::new()
     };
-    if tt_move != 0 {
-        Search::pv_first(&mut moves, &Move::from_u16(tt_move));
-    }
-    if entry.flag != TT_EMPTY && entry.key == key && entry.best != 0 {
+    if !tt_move.is_null() {
         search.tt_key_hits += 1;
-        let bm = Move::from_u16(entry.best);
-        if let Some(pos) = moves.iter().position(|&m| m == bm) {
-            moves.swap(0, pos);
+    }
+    // --- Futility: near the leaves, quiet moves cannot lift a hopeless eval to alpha ---
+    let futile = static_eval.is_some_and(|e| {
+        depth <= FUTILITY_MAX_DEPTH && alpha.abs() < MATE_WINDOW && e + margins.futility * depth as i32 <= alpha
+    });
+    // along the previous PV its move goes first, ahead of the TT move
+    let first = if pv_move.is_null() { tt_move } else { pv_move };
+    let mut picker = MovePicker::new(first, search.killers_at(node_ply), in_check);
+    // computed on the first move that needs it
+    let mut check_info: Option<CheckInfo> = None;
+    let mut i = 0;
+    while let Some(m) = picker.next(search, board, generator) {
+        if m == tt_move {
             search.tt_move_used += 1;
         }
-    }
-    search.order_moves_range(&mut moves, board, node_ply);
-    for (i, m) in moves.iter().copied().enumerate() {
         let enemy = if board.turn == 0 { 1 } else { 0 };
         let enemy_king_idx = if enemy == 0 {
             PieceIndex::K.index()
@@ -109,41 +190,49 @@ pub fn alphabeta(
             );
             // board.print();
         }
-        board.push(m, &generator, &search.nnue);
+        // at least one move is searched so mates and stalemates are still told apart
+        if futile
+            && i > 0
+            && !m.iscapture()
+            && !m.isprom()
+            && !generator.gives_check_with(board, m, check_info.get_or_insert_with(|| generator.check_info(board)))
+        {
+            search.futility_prunes += 1;
+            continue;
+        }
+        // --- LMR: late quiet moves searched at reduced depth first ---
+        // Never reduce checking moves; they are recognised before the move is made.
+        let reduce = depth >= 4
+            && !in_check
+            && m.isquiet()
+            && i >= 4
+            && !generator.gives_check_with(board, m, check_info.get_or_insert_with(|| generator.check_info(board)));
+
+        search.set_follow_pv(!pv_move.is_null() && m == pv_move);
+        search.make_move(board, m, generator);
         search.debug_after_push(board, generator, m);
 
         let mut score: i32;
-
-        // --- LMR: late quiet moves searched at reduced depth first ---
-
-        // --- LMR: late quiet moves searched at reduced depth first ---
-        if depth >= 4 && !in_check && m.isquiet() && i >= 4 {
-            // Robust "gives check" (see Patch 3): compute attacks by the mover.
-            // After push(), board.turn is the side-to-move (the opponent).
-            // let kingidx = if board.turn == 0 { PieceIndex::K.index() } else { PieceIndex::k.index() };
-            // let gives_check = (board.attacked[board.turn as usize] & board.pieces[kingidx]) != 0;
-            let gives_check = generator.in_check(board);
-            if gives_check {
-                // Never reduce checking moves.
+        if reduce {
+            // Reduced-depth NULL-WINDOW search (critical fix)
+            // depth>=4 guarantees depth-2 is valid.
+            score = -alphabeta(search, board, depth - 2, generator, -alpha - 1, -alpha);
+            search.lmr_reductions += 1;
+
+            // Re-search ONLY on fail-high (critical fix)
+            if score > alpha {
                 score = -alphabeta(search, board, depth - 1, generator, -beta, -alpha);
-            } else {
-                // Reduced-depth NULL-WINDOW search (critical fix)
-                // depth>=4 guarantees depth-2 is valid.
-                score = -alphabeta(search, board, depth - 2, generator, -alpha - 1, -alpha);
-                search.lmr_reductions += 1;
-
-                // Re-search ONLY on fail-high (critical fix)
-                if score > alpha {
-                    score = -alphabeta(search, board, depth - 1, generator, -beta, -alpha);
-                    search.lmr_researches += 1;
-                }
+                search.lmr_researches += 1;
             }
         } else {
             score = -alphabeta(search, board, depth - 1, generator, -beta, -alpha);
         }
 
-        board.pop(generator, &search.nnue);
+        search.unmake_move(board);
 
+        if search.stopped() {
+            return 0;
+        }
         if score >= beta {
             search.store_killer(node_ply, m);
             search.store_history_cutoff(m, depth);
@@ -155,6 +244,17 @@ pub fn alphabeta(
         if score > alpha {
             alpha = score;
             best_move = m;
+            search.update_pv(sply, m);
+        }
+        i += 1;
+    }
+
+    if i == 0 {
+        // check for checkmate or stalemate
+        if in_check {
+            return -MATE + board.ply as i32; // checkmate score
+        } else {
+            return search.draw_score(board); // stalemate
         }
     }
 
@@ -180,6 +280,9 @@ fn qsearch(
     qply: u8,
 ) -> i32 {
     search.qnodes += 1;
+    if search.should_stop() {
+        return 0;
+    }
     const QPLY_MAX: u8 = 8;
     if qply >= QPLY_MAX {
         // return evaluate(board, generator);
@@ -217,7 +320,7 @@ fn qsearch(
 
     if moves.is_empty() {
         if in_check {
-            return -99999 + board.ply as i32;
+            return -MATE + board.ply as i32;
         } else {
             return alpha; // no captures/promotions; stand_pat already handled alpha
         }
@@ -229,9 +332,13 @@ fn qsearch(
             "generate_qcaptures produced a non-tactical move"
         );
     }
-    // Order tacticals for qsearch (reuse your existing ordering logic)
+    // Order tacticals for qsearch: captures that lose material by SEE go last.
     // NOTE: ply is still board.ply at this node.
-    search.order_moves(&mut moves, board, board.ply as usize);
+    if in_check {
+        search.order_moves(&mut moves, board, board.ply as usize);
+    } else {
+        moves.sort_by_score(|m| Search::capture_score(m, board, generator));
+    }
 
     for m in moves {
         // Per-move delta pruning (only when not in check).
@@ -259,64 +366,19 @@ fn qsearch(
             }
         }
 
-        if let Some(_stand_pat) = stand_pat_opt {
-            if !in_check && qply >= 1 && m.iscapture() && !m.isprom() {
-                let mover = board.piecelocs.piece_at(m.getSrc());
-                let mover_val = piece_cp(mover.get_piece_type());
-
-                let captured_val = if m.isep() {
-                    100
-                } else {
-                    let cap = board.piecelocs.piece_at(m.getDst());
-                    if cap != crate::core::piece::Piece::None {
-                        piece_cp(cap.get_piece_type())
-                    } else {
-                        0
-                    }
-                };
-
-                // GATE FIRST: only if capturing down do we pay for defense test.
-                if mover_val > captured_val + BAD_CAP_MARGIN {
-                    let us = board.turn;
-                    let them = us ^ 1;
-                    let dst = m.getDst();
-
-                    let src_bb = 1u64 << (m.getSrc() as u64);
-                    let dst_bb = 1u64 << (dst as u64);
-
-                    // base: move piece src->dst
-                    let mut occ_after = (board.occupied ^ src_bb) | dst_bb;
-
-                    // if normal capture, remove captured piece on dst (it was included in occupied already)
-                    // BUT since we set dst_bb, we don’t need to clear it; occupied already had dst_bb set.
-                    // For sliders, the key change is src cleared, not dst cleared. Still, handle EP properly.
-
-                    if m.isep() {
-                        // EP removes the pawn behind the EP square.
-                        // You must compute the actual captured pawn square.
-                        // Typical: if us==0 (white capturing), captured pawn is dst-8; else dst+8.
-                        let us = board.turn;
-                        let cap_sq = if us == 0 {
-                            m.getDst() - 8
-                        } else {
-                            m.getDst() + 8
-                        };
-                        occ_after &= !(1u64 << (cap_sq as u64));
-                    }
-                    // Targeted: is dst defended by the enemy, given current occ?
-                    // If yes, this capture is very likely losing noise in qsearch.
-                    if generator.is_square_attacked_by(board, occ_after, them, dst) {
-                        continue;
-                    }
-                }
-            }
+        // Below the first qsearch ply, captures that lose material by SEE are not worth resolving.
+        if stand_pat_opt.is_some() && qply >= 1 && !generator.see_ge(board, m, 0) {
+            continue;
         }
-        board.push(m, generator, &search.nnue);
+        search.make_move(board, m, generator);
 
         let score = -qsearch(search, board, generator, -beta, -alpha, qply + 1);
 
-        board.pop(generator, &search.nnue);
+        search.unmake_move(board);
 
+        if search.stopped() {
+            return 0;
+        }
         if score >= beta {
             return beta; // fail-hard
         }
@@ -328,6 +390,25 @@ fn qsearch(
     alpha
 }
 
+const RFP_MAX_DEPTH: u8 = 6;
+const RAZOR_MAX_DEPTH: u8 = 2;
+const FUTILITY_MAX_DEPTH: u8 = 3;
+const NULL_MIN_DEPTH: u8 = 3;
+const NULL_VERIFY_DEPTH: u8 = 10;
+
+#[inline(always)]
+fn after_null_move(board: &Board) -> bool {
+    board.history.last().is_some_and(|u| u.mv.is_null())
+}
+
+// Knights, bishops, rooks or queens for the side to move.
+#[inline(always)]
+fn has_non_pawn_material(board: &Board) -> bool {
+    let base = 6 * board.turn as usize;
+    let p = &board.pieces;
+    p[base + PieceIndex::N.index()] | p[base + PieceIndex::B.index()] | p[base + PieceIndex::R.index()] | p[base + PieceIndex::Q.index()] != 0
+}
+
 #[inline(always)]
 fn score_to_tt(score: i32, ply: u16) -> i32 {
     if score > MATE_WINDOW {
@@ -363,4 +444,3 @@ fn piece_cp(pt: crate::core::piece::PieceType) -> i32 {
     }
 }
 const DELTA_MARGIN: i32 = 50;
-const BAD_CAP_MARGIN: i32 = 120; // ~one pawn + change
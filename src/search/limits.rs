@@ -0,0 +1,92 @@
+use std::time::{Duration, Instant};
+
+/// What bounds a search: the fields of a UCI `go` command. Times are in milliseconds;
+/// `None` means no limit of that kind. With nothing set the search runs to `MAX_DEPTH`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SearchLimits {
+    pub depth: Option<u8>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<u64>,
+    pub wtime: Option<u64>,
+    pub btime: Option<u64>,
+    pub winc: Option<u64>,
+    pub binc: Option<u64>,
+    pub movestogo: Option<u64>,
+    /// Search until stopped; clocks are ignored.
+    pub infinite: bool,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u8) -> Self {
+        Self { depth: Some(depth), ..Self::default() }
+    }
+
+    pub fn movetime(ms: u64) -> Self {
+        Self { movetime: Some(ms), ..Self::default() }
+    }
+
+    pub fn nodes(nodes: u64) -> Self {
+        Self { nodes: Some(nodes), ..Self::default() }
+    }
+}
+
+/// Turns the limits into two budgets. The hard one is a deadline the search polls and
+/// unwinds at. The soft one is checked between iterations: a best move that has held for
+/// a few iterations stops early, a falling score or a changing best move buys more time.
+#[derive(Debug, Clone, Copy)]
+pub struct TimeManager {
+    start: Instant,
+    soft: Option<Duration>,
+    hard: Option<Duration>,
+}
+
+impl TimeManager {
+    pub fn new(limits: &SearchLimits, turn: u8, start: Instant) -> Self {
+        let (soft, hard) = Self::allocate(limits, turn);
+        Self { start, soft, hard }
+    }
+
+    fn allocate(limits: &SearchLimits, turn: u8) -> (Option<Duration>, Option<Duration>) {
+        if limits.infinite {
+            return (None, None);
+        }
+        // a fixed move time is spent in full
+        if let Some(ms) = limits.movetime {
+            return (None, Some(Duration::from_millis(ms)));
+        }
+        let (time, inc) = if turn == 0 { (limits.wtime, limits.winc) } else { (limits.btime, limits.binc) };
+        let Some(time) = time else {
+            return (None, None);
+        };
+        let inc = inc.unwrap_or(0);
+        let moves_left = limits.movestogo.unwrap_or(30).max(1);
+
+        // Keep a small reserve so we never flag on GUI/OS latency.
+        let reserve = 50.min(time / 2);
+        let available = time - reserve;
+        let base = time / moves_left + inc * 3 / 4;
+        let soft = base.min(available).max(1);
+        // never bet more than half the clock on one move, unless it is the last before the control
+        let hard = (base * 3).min(if moves_left == 1 { available } else { available / 2 }).max(soft);
+        (Some(Duration::from_millis(soft)), Some(Duration::from_millis(hard)))
+    }
+
+    /// When the search must stop, wherever it is.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.hard.map(|h| self.start + h)
+    }
+
+    /// Called after each completed iteration. `stable` is how many iterations in a row
+    /// have kept the current best move (0: it just changed); `score_drop` is how many
+    /// centipawns the root score lost against the previous iteration.
+    pub fn stop_after_iteration(&self, stable: u32, score_drop: i32) -> bool {
+        let Some(soft) = self.soft else {
+            return false;
+        };
+        const STABILITY_PCT: [u32; 5] = [140, 110, 90, 75, 60];
+        let stability = STABILITY_PCT[stable.min(4) as usize];
+        let falling = 100 + score_drop.clamp(0, 100) as u32;
+        let budget = soft * stability / 100 * falling / 100;
+        self.start.elapsed() >= self.hard.map_or(budget, |h| budget.min(h))
+    }
+}
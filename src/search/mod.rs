@@ -1,4 +1,7 @@
 pub mod search;
 pub mod alphabeta;
 pub mod tt;
+pub mod movepick;
+pub mod limits;
 pub use search::Search;
+pub use limits::SearchLimits;
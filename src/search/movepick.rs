@@ -0,0 +1,153 @@
+use crate::core::{movegen::MoveGenerator, Board, Move, MoveList};
+use crate::search::Search;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Stage {
+    TtMove,
+    GenCaptures,
+    GoodCaptures,
+    Killers,
+    GenQuiets,
+    Quiets,
+    BadCaptures,
+    GenEvasions,
+    Evasions,
+    Done,
+}
+
+/// Hands out the moves of a node one at a time, best guess first:
+/// TT move, winning/equal captures, killers, quiets by history, losing captures.
+/// Each stage only generates what it needs, so a cutoff on the TT move costs no
+/// generation at all. In check every evasion is generated at once.
+pub struct MovePicker {
+    stage: Stage,
+    tt_move: Move,
+    killers: [Move; 2],
+    killer_idx: usize,
+    moves: MoveList,
+    idx: usize,
+    bad_captures: MoveList,
+    bad_idx: usize,
+}
+
+impl MovePicker {
+    pub fn new(tt_move: Move, killers: [Move; 2], in_check: bool) -> Self {
+        Self {
+            stage: if in_check { Stage::GenEvasions } else { Stage::TtMove },
+            tt_move,
+            killers,
+            killer_idx: 0,
+            moves: MoveList::new(),
+            idx: 0,
+            bad_captures: MoveList::new(),
+            bad_idx: 0,
+        }
+    }
+
+    pub fn next(&mut self, search: &Search, board: &mut Board, mg: &MoveGenerator) -> Option<Move> {
+        loop {
+            match self.stage {
+                Stage::TtMove => {
+                    self.stage = Stage::GenCaptures;
+                    if mg.is_pseudo_legal(board, self.tt_move) && mg.is_legal(board, self.tt_move) {
+                        return Some(self.tt_move);
+                    }
+                    // never handed out, so later stages need not skip it
+                    self.tt_move = Move::new();
+                }
+                Stage::GenCaptures => {
+                    self.moves = mg.generate_qcaptures(board);
+                    self.moves.sort_by_score(|m| Search::tactical_score(m, board));
+                    self.idx = 0;
+                    self.stage = Stage::GoodCaptures;
+                }
+                Stage::GoodCaptures => {
+                    while self.idx < self.moves.len() {
+                        let m = self.moves[self.idx];
+                        self.idx += 1;
+                        if m == self.tt_move {
+                            continue;
+                        }
+                        if !mg.see_ge(board, m, 0) {
+                            self.bad_captures.push(m);
+                            continue;
+                        }
+                        return Some(m);
+                    }
+                    self.stage = Stage::Killers;
+                }
+                Stage::Killers => {
+                    while self.killer_idx < 2 {
+                        let k = self.killers[self.killer_idx];
+                        self.killer_idx += 1;
+                        if k.is_null()
+                            || k == self.tt_move
+                            || (self.killer_idx == 2 && k == self.killers[0])
+                            || !k.isquiet()
+                            || !(mg.is_pseudo_legal(board, k) && mg.is_legal(board, k))
+                        {
+                            // mark as not handed out
+                            self.killers[self.killer_idx - 1] = Move::new();
+                            continue;
+                        }
+                        return Some(k);
+                    }
+                    self.stage = Stage::GenQuiets;
+                }
+                Stage::GenQuiets => {
+                    let all = mg.generate(board);
+                    self.moves.clear();
+                    for m in all {
+                        if !(m.iscapture() || m.isprom()) {
+                            self.moves.push(m);
+                        }
+                    }
+                    self.moves.sort_by_score(|m| search.history_score(m));
+                    self.idx = 0;
+                    self.stage = Stage::Quiets;
+                }
+                Stage::Quiets => {
+                    while self.idx < self.moves.len() {
+                        let m = self.moves[self.idx];
+                        self.idx += 1;
+                        if m == self.tt_move || self.killers.contains(&m) {
+                            continue;
+                        }
+                        return Some(m);
+                    }
+                    self.stage = Stage::BadCaptures;
+                }
+                Stage::BadCaptures => {
+                    if self.bad_idx < self.bad_captures.len() {
+                        self.bad_idx += 1;
+                        return Some(self.bad_captures[self.bad_idx - 1]);
+                    }
+                    self.stage = Stage::Done;
+                }
+                Stage::GenEvasions => {
+                    self.moves = mg.generate(board);
+                    let tt = self.tt_move;
+                    self.moves.sort_by_score(|m| {
+                        if m == tt {
+                            i32::MAX
+                        } else if m.iscapture() || m.isprom() {
+                            1 << 24 | Search::tactical_score(m, board)
+                        } else {
+                            search.history_score(m)
+                        }
+                    });
+                    self.idx = 0;
+                    self.stage = Stage::Evasions;
+                }
+                Stage::Evasions => {
+                    if self.idx < self.moves.len() {
+                        self.idx += 1;
+                        return Some(self.moves[self.idx - 1]);
+                    }
+                    self.stage = Stage::Done;
+                }
+                Stage::Done => return None,
+            }
+        }
+    }
+}
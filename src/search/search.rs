@@ -1,12 +1,42 @@
-use crate::core::{movegen::MoveGenerator, Board, Move, Piece, PieceIndex, PieceType};
-use crate::evaluate::nnue::Nnue;
+use crate::core::{movegen::MoveGenerator, Board, Move, MoveList, Piece, PieceIndex, PieceType};
+use crate::evaluate::nnue::{AccumulatorStack, Nnue, DEFAULT_NNUE_PATH};
 use crate::evaluate::{evaluate, evaluate_neural, evaluate_neural_fast};
 use crate::perf;
 use crate::search::alphabeta::alphabeta;
-use crate::search::tt::TranspositionTable;
+use crate::search::limits::{SearchLimits, TimeManager};
+use crate::search::tt::{TranspositionTable, TT_EMPTY};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
 
 const MAX_PLY: usize = 128;
 
+/// Deepest iteration `search_iterative` will run when the caller has no depth limit.
+pub const MAX_DEPTH: u8 = 64;
+
+/// Mate score at ply 0; mated nodes score `-MATE + ply`.
+pub const MATE: i32 = 99999;
+/// Scores beyond this magnitude are mate scores.
+pub const MATE_WINDOW: i32 = 90000;
+
+/// Static-eval margins for the shallow-depth prunings in `alphabeta`, in centipawns per
+/// ply of remaining depth. Exposed as UCI options for tuning.
+#[derive(Debug, Clone, Copy)]
+pub struct PruneMargins {
+    /// Reverse futility: fail high when `eval - rfp * depth >= beta`.
+    pub rfp: i32,
+    /// Futility: skip quiet moves when `eval + futility * depth <= alpha`.
+    pub futility: i32,
+    /// Razoring: verify with qsearch when `eval + razor * depth < alpha`.
+    pub razor: i32,
+}
+
+impl Default for PruneMargins {
+    fn default() -> Self {
+        Self { rfp: 90, futility: 120, razor: 300 }
+    }
+}
+
 pub struct Search {
     pub nodes: u64,
     pub qnodes: u64,
@@ -15,9 +45,25 @@ pub struct Search {
     pub pvs_researches: u64,
     pub asp_fail_low: u64,
     pub asp_fail_high: u64,
+    pub null_tries: u64,
+    pub null_cutoffs: u64,
+    pub null_verifications: u64,
+    pub rfp_cutoffs: u64,
+    pub razor_cutoffs: u64,
+    pub futility_prunes: u64,
     killers: [[Move; 2]; MAX_PLY],
     history: [[i32; 64]; 64],
 
+    /// Triangular PV table: row `p` holds the best line found from `p` plies below the
+    /// root, in columns `p..pv_len[p]`.
+    pv_table: [[Move; MAX_PLY]; MAX_PLY],
+    pv_len: [usize; MAX_PLY],
+    /// Line of the previous iteration, searched first while the path still follows it.
+    prev_pv: Vec<Move>,
+    follow_pv: bool,
+    /// Principal variation of the last finished search, best move first.
+    pub pv: Vec<Move>,
+
     pub tt_probes: u64,
     pub tt_hits: u64,
     pub tt_key_hits: u64,
@@ -26,14 +72,57 @@ pub struct Search {
     pub tt_cut_lower: u64,
     pub tt_cut_upper: u64,
     pub tt_move_used: u64,
-    pub tt: TranspositionTable,
+    /// Shared with the helper threads.
+    pub tt: Arc<TranspositionTable>,
 
+    /// Evaluate with the network when one is loaded; classical eval otherwise.
     pub use_nnue: bool,
-    pub nnue: Nnue,
+    pub nnue: Option<Arc<Nnue>>,
+    /// NNUE accumulators for the current line, one entry per ply from the root.
+    acc: AccumulatorStack,
+
+    /// Centipawns the engine gives up to avoid a draw (negative = draw-seeking).
+    pub contempt: i32,
+    pub margins: PruneMargins,
+    /// Report castling king-takes-rook in `info` lines (UCI_Chess960).
+    pub chess960: bool,
+    root_turn: u8,
+    root_ply: u16,
+
+    /// Shared with the front-end; setting it makes the running search unwind.
+    pub stop: Arc<AtomicBool>,
+    /// Stop once this many nodes have been searched (0 = no limit).
+    node_limit: u64,
+    /// Stop once this instant has passed.
+    deadline: Option<Instant>,
+    stopped: bool,
+    /// Inside a null-move verification search, where null moves are off.
+    pub(crate) null_verifying: bool,
+
+    /// Search threads, this one included.
+    pub threads: usize,
+    /// 0 for the thread that reports and manages time, 1.. for helpers.
+    thread_id: usize,
+    /// Nodes of all threads in the current search, flushed every 1024 nodes.
+    pool_nodes: Arc<AtomicU64>,
+    flushed_nodes: u64,
+    /// `pool_nodes` as of this thread's last flush.
+    pool_seen: u64,
 }
 
 impl Search {
+    /// With `use_nnue` the default network is loaded if it exists; without one
+    /// the search simply runs on classical eval.
     pub fn new(use_nnue: bool) -> Self {
+        let nnue = if use_nnue { Nnue::load(DEFAULT_NNUE_PATH).ok() } else { None };
+        Self::with_nnue(use_nnue, nnue)
+    }
+
+    pub fn with_nnue(use_nnue: bool, nnue: Option<Nnue>) -> Self {
+        Self::with_tt(use_nnue, nnue.map(Arc::new), Arc::new(TranspositionTable::new_mb(128)))
+    }
+
+    fn with_tt(use_nnue: bool, nnue: Option<Arc<Nnue>>, tt: Arc<TranspositionTable>) -> Self {
         let null = Move::new();
         let killers = std::array::from_fn(|_| [null; 2]);
         let history = [[0i32; 64]; 64];
@@ -45,9 +134,20 @@ impl Search {
             pvs_researches: 0,
             asp_fail_low: 0,
             asp_fail_high: 0,
+            null_tries: 0,
+            null_cutoffs: 0,
+            null_verifications: 0,
+            rfp_cutoffs: 0,
+            razor_cutoffs: 0,
+            futility_prunes: 0,
             killers,
             history,
-            tt: TranspositionTable::new_mb(128),
+            pv_table: [[null; MAX_PLY]; MAX_PLY],
+            pv_len: [0; MAX_PLY],
+            prev_pv: Vec::new(),
+            follow_pv: false,
+            pv: Vec::new(),
+            tt,
             tt_probes: 0,
             tt_hits: 0,
             tt_key_hits: 0,
@@ -57,25 +157,136 @@ impl Search {
             tt_cut_upper: 0,
             tt_move_used: 0,
             use_nnue,
-            nnue: Nnue::load("data/processed/nnue.bin").expect("failed to load NNUE file"),
+            nnue,
+            acc: AccumulatorStack::new(),
+            contempt: 0,
+            margins: PruneMargins::default(),
+            chess960: false,
+            root_turn: 0,
+            root_ply: 0,
+            stop: Arc::new(AtomicBool::new(false)),
+            node_limit: 0,
+            deadline: None,
+            stopped: false,
+            null_verifying: false,
+            threads: 1,
+            thread_id: 0,
+            pool_nodes: Arc::new(AtomicU64::new(0)),
+            flushed_nodes: 0,
+            pool_seen: 0,
         }
     }
 
+    /// Forget everything learned from the previous game (TT, killers, history).
+    pub fn new_game(&mut self) {
+        self.tt.clear();
+        self.killers = std::array::from_fn(|_| [Move::new(); 2]);
+        self.history = [[0i32; 64]; 64];
+    }
+
+    /// Checks the node limit at every node. The stop flag and the clock are polled every
+    /// 1024 nodes, when this thread's nodes are also added to the pool count the node
+    /// limit applies to. Once this returns true the current iteration is abandoned.
     #[inline(always)]
-    pub fn eval(&self, board: &Board, mg: &MoveGenerator) -> i32 {
-        if self.use_nnue {
-            evaluate_neural(board, &self.nnue)
-        } else {
-            evaluate(board, mg)
+    pub(crate) fn should_stop(&mut self) -> bool {
+        if self.stopped {
+            return true;
+        }
+        let searched = self.nodes + self.qnodes;
+        // other threads' nodes are only seen as of the last flush
+        if self.node_limit != 0 && self.pool_seen + searched - self.flushed_nodes >= self.node_limit {
+            self.stopped = true;
+        } else if searched & 1023 == 0 {
+            let new = searched - self.flushed_nodes;
+            self.pool_seen = self.pool_nodes.fetch_add(new, Ordering::Relaxed) + new;
+            self.flushed_nodes = searched;
+            self.stopped = self.stop.load(Ordering::Relaxed) || self.deadline.is_some_and(|d| Instant::now() >= d);
         }
+        self.stopped
+    }
+
+    #[inline(always)]
+    pub(crate) fn stopped(&self) -> bool {
+        self.stopped
     }
 
+    /// Score of a drawn node from the side to move's point of view.
+    /// Contempt makes draws look bad for the side the engine is playing.
     #[inline(always)]
-    pub fn eval_fast(&self, board: &Board, mg: &MoveGenerator) -> i32 {
-        if self.use_nnue {
-            evaluate_neural_fast(board, &self.nnue)
+    pub(crate) fn draw_score(&self, board: &Board) -> i32 {
+        if board.turn == self.root_turn {
+            -self.contempt
         } else {
-            evaluate(board, mg)
+            self.contempt
+        }
+    }
+
+    /// The network in use, if any.
+    #[inline(always)]
+    pub fn net(&self) -> Option<&Nnue> {
+        self.nnue.as_deref().filter(|_| self.use_nnue)
+    }
+
+    /// Starts the accumulator stack at the search root.
+    fn reset_accumulators(&mut self, board: &Board) {
+        if let Some(nnue) = self.nnue.as_ref().filter(|_| self.use_nnue) {
+            self.acc.reset(nnue, board);
+        }
+    }
+
+    /// Plays `m`, recording its NNUE deltas only when a network is in use.
+    #[inline(always)]
+    pub(crate) fn make_move(&mut self, board: &mut Board, m: Move, mg: &MoveGenerator) {
+        match self.nnue.as_ref().filter(|_| self.use_nnue) {
+            Some(nnue) => {
+                self.acc.push(board, m);
+                board.push(m, mg);
+                self.acc.refresh_king_move(nnue, board);
+            }
+            None => board.push(m, mg),
+        }
+    }
+
+    /// Passes the turn (see `Board::push_null`).
+    #[inline(always)]
+    pub(crate) fn make_null_move(&mut self, board: &mut Board) {
+        if self.net().is_some() {
+            self.acc.push_null();
+        }
+        board.push_null();
+    }
+
+    /// Takes back a `make_null_move`.
+    #[inline(always)]
+    pub(crate) fn unmake_null_move(&mut self, board: &mut Board) {
+        if self.net().is_some() {
+            self.acc.pop();
+        }
+        board.pop_null();
+    }
+
+    /// Takes back the last `make_move`.
+    #[inline(always)]
+    pub(crate) fn unmake_move(&mut self, board: &mut Board) {
+        if self.net().is_some() {
+            self.acc.pop();
+        }
+        board.pop();
+    }
+
+    #[inline(always)]
+    pub fn eval(&mut self, board: &Board, mg: &MoveGenerator) -> i32 {
+        match self.nnue.as_ref().filter(|_| self.use_nnue) {
+            Some(nnue) => evaluate_neural(self.acc.current(nnue, board), board.turn, nnue),
+            None => evaluate(board, mg),
+        }
+    }
+
+    #[inline(always)]
+    pub fn eval_fast(&mut self, board: &Board, mg: &MoveGenerator) -> i32 {
+        match self.nnue.as_ref().filter(|_| self.use_nnue) {
+            Some(nnue) => evaluate_neural_fast(self.acc.current(nnue, board), board.turn, nnue),
+            None => evaluate(board, mg),
         }
     }
 
@@ -85,14 +296,15 @@ impl Search {
         depth: u8,
         mg: &MoveGenerator,
     ) -> (Move, i32) {
+        self.reset_accumulators(board);
         let moves = mg.generate(board);
         let mut best_score = -30000;
         let mut best_move = Move::new();
         for m in moves {
-            board.push(m, &mg, &self.nnue);
+            self.make_move(board, m, mg);
             //self.debug_after_push(board, mg, m);
             let score = -alphabeta(self, board, depth - 1, mg, -30000, 30000);
-            board.pop(mg, &self.nnue);
+            self.unmake_move(board);
             if score > best_score {
                 best_move = m;
                 best_score = score;
@@ -106,17 +318,123 @@ impl Search {
         return self.search_iterative(board, depth, mg);
     }
 
+    /// Iterative deepening to `max_depth`, with no node or time limit.
     pub fn search_iterative(
         &mut self,
         board: &mut Board,
         max_depth: u8,
         mg: &MoveGenerator,
     ) -> (Move, i32) {
-        const INF: i32 = 30_000;
+        self.search_with_limits(board, &SearchLimits::depth(max_depth), mg)
+    }
 
+    /// Iterative deepening until `limits` or the stop flag end it. The stop flag is not
+    /// cleared here; the front-end resets it before starting a search.
+    ///
+    /// With `threads` > 1 this is Lazy SMP: helpers run the same iterations on their own
+    /// copies of the board, with their own killers and history, and meet only in the shared
+    /// TT. They stop when this thread does; the deepest finished iteration of any thread
+    /// gives the answer.
+    pub fn search_with_limits(
+        &mut self,
+        board: &mut Board,
+        limits: &SearchLimits,
+        mg: &MoveGenerator,
+    ) -> (Move, i32) {
         perf::reset();
+        let start = Instant::now();
+        let time = TimeManager::new(limits, board.turn, start);
+        self.deadline = time.deadline();
+        self.node_limit = limits.nodes.unwrap_or(0);
+        self.pool_nodes = Arc::new(AtomicU64::new(0));
+        let max_depth = if limits.infinite { MAX_DEPTH } else { limits.depth.unwrap_or(MAX_DEPTH) };
+
+        let (mut best, mut score) = if self.threads <= 1 {
+            let (best, score, _) = self.iterate(board, max_depth, mg, Some(&time), start);
+            (best, score)
+        } else {
+            let done = Arc::new(AtomicBool::new(false));
+            let mut helpers: Vec<Search> = (1..self.threads).map(|id| self.helper(id, &done)).collect();
+            let (best, score, _, from) = std::thread::scope(|scope| {
+                let handles: Vec<_> = helpers
+                    .iter_mut()
+                    .map(|helper| {
+                        let mut board = board.clone();
+                        scope.spawn(move || helper.iterate(&mut board, max_depth, mg, None, start))
+                    })
+                    .collect();
+                let main = self.iterate(board, max_depth, mg, Some(&time), start);
+                done.store(true, Ordering::Relaxed);
+                handles
+                    .into_iter()
+                    .map(|h| h.join().expect("search thread panicked"))
+                    .enumerate()
+                    // ties go to the main thread, which comes first
+                    .fold((main.0, main.1, main.2, 0), |best, (i, r)| {
+                        if r.2 > best.2 && !r.0.is_null() { (r.0, r.1, r.2, i + 1) } else { best }
+                    })
+            });
+            if from > 0 {
+                self.pv = std::mem::take(&mut helpers[from - 1].pv);
+            }
+            (best, score)
+        };
+
+        // Stopped before depth 1 finished: still answer with a legal move.
+        if best.is_null() {
+            if let Some(&m) = mg.generate(board).first() {
+                best = m;
+                score = 0;
+                self.pv = vec![m];
+            }
+        }
+        (best, score)
+    }
 
-        let mut pv: Option<Move> = None;
+    // A searcher for helper thread `id`: shares the TT, the net and the node count, and
+    // stops when `done` is set.
+    fn helper(&self, id: usize, done: &Arc<AtomicBool>) -> Search {
+        let mut helper = Self::with_tt(self.use_nnue, self.nnue.clone(), Arc::clone(&self.tt));
+        helper.contempt = self.contempt;
+        helper.margins = self.margins;
+        helper.chess960 = self.chess960;
+        helper.thread_id = id;
+        helper.stop = Arc::clone(done);
+        helper.pool_nodes = Arc::clone(&self.pool_nodes);
+        helper
+    }
+
+    /// Runs iterations up to `max_depth`, printing `info` lines on the main thread.
+    /// Returns the best move and score of the deepest finished iteration, and its depth
+    /// (0 with a partial result when none finished); its line is left in `self.pv`.
+    fn iterate(
+        &mut self,
+        board: &mut Board,
+        max_depth: u8,
+        mg: &MoveGenerator,
+        time: Option<&TimeManager>,
+        start: Instant,
+    ) -> (Move, i32, u8) {
+        // above every mate score, so a mate found at the root is never cut to a bound
+        const INF: i32 = MATE + 1;
+
+        self.nodes = 0;
+        self.qnodes = 0;
+        self.flushed_nodes = 0;
+        self.pool_seen = 0;
+        self.stopped = false;
+        self.root_turn = board.turn;
+        self.root_ply = board.ply;
+        let root_ply = board.ply;
+        // iterations in a row that kept the best move
+        let mut stable = 0u32;
+        let mut completed = 0;
+        // odd helpers start one iteration ahead so the threads spread over two depths
+        let first_depth = if self.thread_id % 2 == 1 { 2.min(max_depth) } else { 1 };
+
+        self.reset_accumulators(board);
+
+        let mut pv: Vec<Move> = Vec::new();
         let mut prev_score: i32 = 0;
 
         let window: i32 = 25;
@@ -124,22 +442,22 @@ impl Search {
         let mut final_best = Move::new();
         let mut final_score = 0;
 
-        for depth in 1..=max_depth {
-            self.nodes = 0;
-
+        for depth in first_depth..=max_depth {
             // Root search runner (kept inside this function).
             // Runs ONE root search at this depth with the provided bounds.
-            let mut run_root = |mut alpha: i32, beta: i32, pv: Option<Move>| -> (Move, i32) {
+            self.prev_pv.clone_from(&pv);
+            let mut run_root = |mut alpha: i32, beta: i32, pv: Option<Move>| -> (Move, i32, bool) {
+                self.clear_pv(0);
                 let mut moves = mg.generate(board);
 
                 // Handle mate/stalemate at root cleanly
                 if moves.is_empty() {
                     let score = if mg.in_check(board) {
-                        -99999 + board.ply as i32
+                        -MATE + board.ply as i32
                     } else {
                         0
                     };
-                    return (Move::new(), score);
+                    return (Move::new(), score, false);
                 }
 
                 // PV-first
@@ -155,7 +473,8 @@ impl Search {
                 let mut best_score = -INF;
 
                 for (i, m) in moves.iter().copied().enumerate() {
-                    board.push(m, mg, &self.nnue);
+                    self.follow_pv = pv == Some(m);
+                    self.make_move(board, m, mg);
 
                     let score = if i == 0 {
                         // First move: full window
@@ -171,11 +490,16 @@ impl Search {
                         s
                     };
 
-                    board.pop(mg, &self.nnue);
+                    self.unmake_move(board);
 
+                    // An interrupted subtree returns garbage; keep only fully searched moves.
+                    if self.stopped {
+                        break;
+                    }
                     if score > best_score {
                         best_score = score;
                         best_move = m;
+                        self.update_pv(0, m);
                     }
                     if score > alpha {
                         alpha = score;
@@ -185,43 +509,182 @@ impl Search {
                     }
                 }
 
-                (best_move, best_score)
+                (best_move, best_score, self.stopped)
             };
 
             // --- Aspiration window attempt #1 ---
-            let a0 = prev_score - window;
-            let b0 = prev_score + window;
-
-            let (mut best_move, mut best_score) = run_root(a0, b0, pv);
-
-            // --- If failed, widen and re-search once ---
-            if best_score <= a0 {
-                // fail-low
-                (best_move, best_score) = run_root(-INF, b0, pv);
-                self.asp_fail_low += 1;
-            } else if best_score >= b0 {
-                // fail-high
-                (best_move, best_score) = run_root(a0, INF, pv);
-                self.asp_fail_high += 1;
+            let mut a0 = prev_score - window;
+            let mut b0 = prev_score + window;
+
+            let root_pv = pv.first().copied();
+            let (mut best_move, mut best_score, mut stopped) = run_root(a0, b0, root_pv);
+
+            // --- On a fail, open that side fully and re-search, until the score is exact ---
+            // (a bound has no complete PV behind it)
+            let (mut fail_low, mut fail_high) = (0, 0);
+            while !stopped && ((best_score <= a0 && a0 > -INF) || (best_score >= b0 && b0 < INF)) {
+                if best_score <= a0 {
+                    a0 = -INF;
+                    fail_low += 1;
+                } else {
+                    b0 = INF;
+                    fail_high += 1;
+                }
+                (best_move, best_score, stopped) = run_root(a0, b0, root_pv);
+            }
+            self.asp_fail_low += fail_low;
+            self.asp_fail_high += fail_high;
+
+            if stopped {
+                // Unfinished iteration: only use it if we have nothing better.
+                if final_best.is_null() && !best_move.is_null() {
+                    final_best = best_move;
+                    final_score = best_score;
+                    pv = vec![best_move];
+                }
+                break;
             }
 
-            pv = Some(best_move);
+            stable = if root_pv == Some(best_move) { stable + 1 } else { 0 };
+            let score_drop = if depth > 1 { prev_score - best_score } else { 0 };
+            pv = self.pv_table[0][..self.pv_len[0]].to_vec();
+            self.extend_pv_from_tt(board, &mut pv, mg);
             prev_score = best_score;
 
             final_best = best_move;
             final_score = best_score;
-            println!("Searched to depth {}: PV: ", depth);
-            final_best.print();
+            completed = depth;
+            if self.thread_id == 0 {
+                self.print_info(depth, final_score, root_ply, start, &pv);
+            }
+
+            if final_best.is_null() {
+                break; // mate or stalemate at the root
+            }
+            if time.is_some_and(|t| t.stop_after_iteration(stable, score_drop)) {
+                break;
+            }
         }
+        self.pv = pv;
+        (final_best, final_score, completed)
+    }
+
+    /// Prints one UCI `info` line for a completed iteration.
+    fn print_info(&self, depth: u8, score: i32, root_ply: u16, start: Instant, pv: &[Move]) {
+        let nodes = self.pool_nodes.load(Ordering::Relaxed) + self.nodes + self.qnodes - self.flushed_nodes;
+        let elapsed = start.elapsed();
+        let nps = (nodes as f64 / elapsed.as_secs_f64().max(1e-6)) as u64;
         println!(
-            "nodes={} qnodes={} lmr_red={} lmr_re={} pvs_re={} aspL={} aspH={}",
+            "info depth {} score {} nodes {} nps {} time {} pv {}",
+            depth,
+            Self::uci_score(score, root_ply),
+            nodes,
+            nps,
+            elapsed.as_millis(),
+            Self::pv_to_uci(pv, self.chess960)
+        );
+    }
+
+    /// A line of moves in UCI notation, space separated.
+    pub fn pv_to_uci(pv: &[Move], chess960: bool) -> String {
+        pv.iter().map(|m| m.to_uci_mode(chess960)).collect::<Vec<_>>().join(" ")
+    }
+
+    /// Plies from the search root to `board`.
+    #[inline(always)]
+    pub(crate) fn search_ply(&self, board: &Board) -> usize {
+        (board.ply - self.root_ply) as usize
+    }
+
+    /// Empties the PV of the node `sply` plies below the root.
+    #[inline(always)]
+    pub(crate) fn clear_pv(&mut self, sply: usize) {
+        if sply < MAX_PLY {
+            self.pv_len[sply] = sply;
+        }
+    }
+
+    /// `m` is the new best move at `sply`: its line becomes `m` followed by the child's.
+    #[inline(always)]
+    pub(crate) fn update_pv(&mut self, sply: usize, m: Move) {
+        if sply + 1 >= MAX_PLY {
+            return;
+        }
+        let (rows, below) = self.pv_table.split_at_mut(sply + 1);
+        let len = self.pv_len[sply + 1].max(sply + 1);
+        rows[sply][sply] = m;
+        rows[sply][sply + 1..len].copy_from_slice(&below[0][sply + 1..len]);
+        self.pv_len[sply] = len;
+    }
+
+    /// The previous iteration's move at `sply` if the path to this node followed its line.
+    /// Consumes the flag: children only follow when the parent hands it on.
+    #[inline(always)]
+    pub(crate) fn take_pv_move(&mut self, sply: usize) -> Move {
+        if std::mem::replace(&mut self.follow_pv, false) && sply < self.prev_pv.len() {
+            self.prev_pv[sply]
+        } else {
+            Move::new()
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_follow_pv(&mut self, follow: bool) {
+        self.follow_pv = follow;
+    }
+
+    /// A TT cutoff on the PV leaves the table's line short. Continues it with the TT
+    /// moves of the positions it leads to, each checked for legality, until a miss,
+    /// a repetition or `MAX_PLY`.
+    fn extend_pv_from_tt(&self, board: &mut Board, pv: &mut Vec<Move>, mg: &MoveGenerator) {
+        for &m in pv.iter() {
+            board.push(m, mg);
+        }
+        while pv.len() < MAX_PLY && !board.is_repetition() {
+            let entry = self.tt.probe(board.hash);
+            let m = Move::from_u16(entry.best);
+            if entry.flag == TT_EMPTY || entry.key != board.hash || !mg.is_pseudo_legal(board, m) || !mg.is_legal(board, m) {
+                break;
+            }
+            board.push(m, mg);
+            pv.push(m);
+        }
+        for _ in 0..pv.len() {
+            board.pop();
+        }
+    }
+
+    /// Formats a root score as `cp <x>` or `mate <moves>` (negative when we are mated).
+    /// Mate scores are stored relative to the absolute board ply, so `root_ply` is needed.
+    pub fn uci_score(score: i32, root_ply: u16) -> String {
+        if score > MATE_WINDOW {
+            let plies = MATE - score - root_ply as i32;
+            format!("mate {}", (plies + 1) / 2)
+        } else if score < -MATE_WINDOW {
+            let plies = MATE + score - root_ply as i32;
+            format!("mate -{}", plies / 2)
+        } else {
+            format!("cp {}", score)
+        }
+    }
+
+    /// Dumps search, TT and perf counters (debug tooling; not part of UCI output).
+    pub fn print_stats(&self) {
+        println!(
+            "nodes={} qnodes={} lmr_red={} lmr_re={} pvs_re={} aspL={} aspH={} null={} null_cut={} null_ver={} rfp={} razor={} futile={}",
             self.nodes,
             self.qnodes,
             self.lmr_reductions,
             self.lmr_researches,
             self.pvs_researches,
             self.asp_fail_low,
-            self.asp_fail_high
+            self.asp_fail_high,
+            self.null_tries,
+            self.null_cutoffs,
+            self.null_verifications,
+            self.rfp_cutoffs,
+            self.razor_cutoffs,
+            self.futility_prunes
         );
         println!(
     "TT: probes={} hits={}  ({:.1}%) key_hits={}  exact={} cutL={} cutU={} move_used={} ",
@@ -237,7 +700,6 @@ impl Search {
 
         let snapshot = perf::snapshot();
         perf::print_snapshot("Performance metrics", snapshot);
-        (final_best, final_score)
     }
 
     #[cfg(debug_assertions)]
@@ -291,7 +753,7 @@ impl Search {
         }
     }
     #[inline(always)]
-    pub(crate) fn order_moves(&self, moves: &mut Vec<Move>, board: &Board, ply: usize) {
+    pub(crate) fn order_moves(&self, moves: &mut MoveList, board: &Board, ply: usize) {
         self.order_moves_range(moves, board, ply);
     }
 
@@ -359,7 +821,7 @@ impl Search {
     }
 
     #[inline(always)]
-    fn tactical_score(m: Move, board: &Board) -> i32 {
+    pub(crate) fn tactical_score(m: Move, board: &Board) -> i32 {
         let mover = board.piecelocs.piece_at(m.getSrc());
         let mover_v = Self::piece_value(mover.get_piece_type());
 
@@ -381,6 +843,31 @@ impl Search {
         (cap_v * 100) + (prom_v * 10) - mover_v
     }
 
+    /// `tactical_score` with captures that lose material by SEE pushed below all others.
+    #[inline(always)]
+    pub(crate) fn capture_score(m: Move, board: &Board, mg: &MoveGenerator) -> i32 {
+        let score = Self::tactical_score(m, board);
+        if mg.see_ge(board, m, 0) {
+            score
+        } else {
+            score - (1 << 20)
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn killers_at(&self, ply: usize) -> [Move; 2] {
+        if ply < MAX_PLY {
+            self.killers[ply]
+        } else {
+            [Move::new(); 2]
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn history_score(&self, m: Move) -> i32 {
+        self.history[m.getSrc() as usize][m.getDst() as usize]
+    }
+
     #[inline(always)]
     pub(crate) fn store_killer(&mut self, ply: usize, m: Move) {
         if ply >= MAX_PLY {
@@ -416,7 +903,7 @@ impl Search {
     }
 
     #[inline(always)]
-    fn piece_value(pt: PieceType) -> i32 {
+    pub(crate) fn piece_value(pt: PieceType) -> i32 {
         match pt {
             PieceType::P => 100,
             PieceType::N => 320,
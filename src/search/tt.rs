@@ -1,3 +1,5 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
 #[derive(Copy, Clone)]
 pub struct TTEntry {
     pub key: u64,        // full zobrist key
@@ -18,9 +20,52 @@ impl Default for TTEntry {
     }
 }
 
+// One slot as two words: the entry packed into `data`, and the key XORed with it. A slot
+// written by two threads at once ends up with a key that matches neither, so torn entries
+// read as misses and the table needs no locks.
+#[derive(Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+impl TTEntry {
+    #[inline(always)]
+    fn pack(&self) -> u64 {
+        self.depth as u64 | (self.flag as u64) << 8 | (self.best as u64) << 16 | (self.score as u32 as u64) << 32
+    }
+
+    #[inline(always)]
+    fn unpack(key: u64, data: u64) -> Self {
+        Self {
+            key,
+            depth: data as u8,
+            flag: (data >> 8) as u8,
+            best: (data >> 16) as u16,
+            score: (data >> 32) as u32 as i32,
+        }
+    }
+}
+
+impl Slot {
+    #[inline(always)]
+    fn load(&self) -> TTEntry {
+        let data = self.data.load(Ordering::Relaxed);
+        TTEntry::unpack(self.key.load(Ordering::Relaxed) ^ data, data)
+    }
+
+    #[inline(always)]
+    fn save(&self, e: TTEntry) {
+        let data = e.pack();
+        self.data.store(data, Ordering::Relaxed);
+        self.key.store(e.key ^ data, Ordering::Relaxed);
+    }
+}
+
+/// Shared by all search threads; every method takes `&self`.
 pub struct TranspositionTable {
     bucket_mask: usize,     // masks bucket index (bucket_count - 1)
-    table: Vec<TTEntry>,    // flat array of size bucket_count * WAYS
+    table: Vec<Slot>,       // flat array of size bucket_count * WAYS
 }
 
 impl TranspositionTable {
@@ -28,7 +73,7 @@ impl TranspositionTable {
 
     pub fn new_mb(megabytes: usize) -> Self {
         let bytes = megabytes * 1024 * 1024;
-        let entry_size = std::mem::size_of::<TTEntry>();
+        let entry_size = std::mem::size_of::<Slot>();
 
         // Total entries that fit:
         let mut total_entries = bytes / entry_size;
@@ -51,7 +96,7 @@ impl TranspositionTable {
         }
 
         let table_len = buckets * Self::WAYS;
-        let table = vec![TTEntry::default(); table_len];
+        let table = (0..table_len).map(|_| Slot::default()).collect();
 
         Self {
             bucket_mask: buckets - 1,
@@ -75,7 +120,7 @@ impl TranspositionTable {
         let start = self.bucket_start(key);
         // Scan 4-way bucket
         for j in 0..Self::WAYS {
-            let e = self.table[start + j];
+            let e = self.table[start + j].load();
             if e.flag != TT_EMPTY && e.key == key {
                 return e;
             }
@@ -86,57 +131,56 @@ impl TranspositionTable {
     /// Store entry with simple 4-way replacement policy:
     /// 1) replace same key
     /// 2) else fill empty slot
-    /// 3) else replace shallowest depth in bucket
+    /// 3) else replace shallowest depth
     #[inline(always)]
-    pub fn store(&mut self, key: u64, depth: u8, flag: u8, score: i32, best: u16) {
+    pub fn store(&self, key: u64, depth: u8, flag: u8, score: i32, best: u16) {
         let start = self.bucket_start(key);
         let newe = TTEntry { key, depth, flag, score, best };
+        let bucket = &self.table[start..start + Self::WAYS];
 
         // 1) Same key replacement
-        for j in 0..Self::WAYS {
-            let i = start + j;
-            let e = self.table[i];
+        for slot in bucket {
+            let e = slot.load();
             if e.flag != TT_EMPTY && e.key == key {
                 // Prefer deeper (or equal) info; but also allow updating best move.
                 if depth >= e.depth || flag == TT_EXACT {
-                    self.table[i] = newe;
+                    slot.save(newe);
                 } else if best != 0 && e.best == 0 {
                     // Preserve deeper bounds but keep a move if we didn't have one
                     let mut patched = e;
                     patched.best = best;
-                    self.table[i] = patched;
+                    slot.save(patched);
                 }
                 return;
             }
         }
 
         // 2) Fill empty slot
-        for j in 0..Self::WAYS {
-            let i = start + j;
-            if self.table[i].flag == TT_EMPTY {
-                self.table[i] = newe;
+        for slot in bucket {
+            if slot.load().flag == TT_EMPTY {
+                slot.save(newe);
                 return;
             }
         }
 
         // 3) Replace shallowest depth
-        let mut repl = start;
-        let mut best_depth = self.table[start].depth;
-        for j in 1..Self::WAYS {
-            let i = start + j;
-            let d = self.table[i].depth;
+        let mut repl = 0;
+        let mut best_depth = bucket[0].load().depth;
+        for (j, slot) in bucket.iter().enumerate().skip(1) {
+            let d = slot.load().depth;
             if d < best_depth {
                 best_depth = d;
-                repl = i;
+                repl = j;
             }
         }
 
-        self.table[repl] = newe;
+        bucket[repl].save(newe);
     }
 
-    pub fn clear(&mut self) {
-        for e in self.table.iter_mut() {
-            *e = TTEntry::default();
+    pub fn clear(&self) {
+        for slot in self.table.iter() {
+            slot.data.store(0, Ordering::Relaxed);
+            slot.key.store(0, Ordering::Relaxed);
         }
     }
-}
\ No newline at end of file
+}
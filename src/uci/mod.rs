@@ -1 +1,4 @@
-pub mod stockfish;
\ No newline at end of file
+pub mod stockfish;
+pub mod uci;
+
+pub use uci::{run, Uci};
@@ -0,0 +1,282 @@
+use std::io::{self, BufRead};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::core::{cli, perft};
+use crate::core::movegen::MoveGenerator;
+use crate::core::Board;
+use crate::evaluate::nnue::{Nnue, DEFAULT_NNUE_PATH};
+use crate::search::search::{PruneMargins, MAX_DEPTH};
+use crate::search::tt::TranspositionTable;
+use crate::search::{Search, SearchLimits};
+
+const DEFAULT_HASH_MB: usize = 128;
+const MAX_THREADS: usize = 256;
+const STARTPOS: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// Parameters of a `go` command.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GoParams {
+    pub limits: SearchLimits,
+    /// `go perft <depth>`: print a divide instead of searching.
+    pub perft: Option<u8>,
+}
+
+impl GoParams {
+    pub fn parse(tokens: &[&str]) -> Self {
+        let mut go = GoParams::default();
+        let limits = &mut go.limits;
+        let mut i = 0;
+        while i < tokens.len() {
+            let value = tokens.get(i + 1).and_then(|v| v.parse::<u64>().ok());
+            match tokens[i] {
+                "depth" => limits.depth = value.map(|d| d.clamp(1, MAX_DEPTH as u64) as u8),
+                "nodes" => limits.nodes = value,
+                "movetime" => limits.movetime = value,
+                "wtime" => limits.wtime = value,
+                "btime" => limits.btime = value,
+                "winc" => limits.winc = value,
+                "binc" => limits.binc = value,
+                "movestogo" => limits.movestogo = value,
+                "perft" => go.perft = value.map(|d| d.min(u8::MAX as u64) as u8),
+                "infinite" => {
+                    limits.infinite = true;
+                    i += 1;
+                    continue;
+                }
+                _ => {
+                    i += 1;
+                    continue;
+                }
+            }
+            // a missing or non-numeric value is not consumed, it may be the next keyword
+            i += if value.is_some() { 2 } else { 1 };
+        }
+        go
+    }
+}
+
+/// UCI front-end: owns the game position and the searcher, and runs `go` on a worker thread
+/// so that `stop`, `isready` and `quit` stay responsive while thinking.
+pub struct Uci {
+    mg: Arc<MoveGenerator>,
+    pub(crate) board: Board,
+    pub(crate) search: Option<Search>,
+    worker: Option<JoinHandle<(Search, Board)>>,
+    /// Clone of the searcher's stop flag, usable while the worker owns the searcher.
+    stop: Arc<AtomicBool>,
+}
+
+impl Uci {
+    pub fn new(nnue: Option<Nnue>) -> Self {
+        let search = Search::with_nnue(true, nnue);
+        let mut uci = Self {
+            mg: Arc::new(MoveGenerator::new()),
+            board: Board::new(),
+            stop: Arc::clone(&search.stop),
+            search: Some(search),
+            worker: None,
+        };
+        uci.set_position(&["startpos"]);
+        uci
+    }
+
+    /// Handles one command line. Returns false on `quit`.
+    pub fn handle(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((&cmd, args)) = tokens.split_first() else {
+            return true;
+        };
+
+        match cmd {
+            "uci" => {
+                println!("id name rustychess {}", env!("CARGO_PKG_VERSION"));
+                println!("id author {}", env!("CARGO_PKG_AUTHORS"));
+                println!(
+                    "option name Hash type spin default {} min 1 max 65536",
+                    DEFAULT_HASH_MB
+                );
+                println!("option name Threads type spin default 1 min 1 max {}", MAX_THREADS);
+                println!("option name NNUEFile type string default {}", DEFAULT_NNUE_PATH);
+                println!("option name UseNNUE type check default true");
+                println!("option name Contempt type spin default 0 min -1000 max 1000");
+                let margins = PruneMargins::default();
+                println!("option name RFPMargin type spin default {} min 0 max 1000", margins.rfp);
+                println!("option name FutilityMargin type spin default {} min 0 max 1000", margins.futility);
+                println!("option name RazorMargin type spin default {} min 0 max 1000", margins.razor);
+                println!("option name UCI_Chess960 type check default false");
+                println!("uciok");
+            }
+            "isready" => println!("readyok"),
+            // Commands that need the searcher or the position end a running search first;
+            // only waiting for it would hang on `go infinite`, with `stop` never read.
+            "ucinewgame" => {
+                self.stop();
+                self.search_mut().new_game();
+                self.set_position(&["startpos"]);
+            }
+            "position" => {
+                self.stop();
+                self.set_position(args);
+            }
+            "setoption" => {
+                self.stop();
+                self.set_option(args);
+            }
+            "go" => {
+                self.stop();
+                self.go(GoParams::parse(args));
+            }
+            "stop" => self.stop(),
+            "quit" => {
+                self.stop();
+                return false;
+            }
+            // Non-standard but handy when driving the engine by hand.
+            "d" => self.board.print(),
+            _ => println!("info string unknown command: {}", cmd),
+        }
+        true
+    }
+
+    fn search_mut(&mut self) -> &mut Search {
+        self.search.as_mut().expect("searcher is owned by the worker thread")
+    }
+
+    /// `position [startpos | fen <fen>] [moves <m1> ... <mn>]`
+    fn set_position(&mut self, args: &[&str]) {
+        let moves_at = args.iter().position(|&t| t == "moves").unwrap_or(args.len());
+        let fen = match args.first() {
+            Some(&"startpos") => STARTPOS.to_string(),
+            Some(&"fen") => args[1..moves_at].join(" "),
+            _ => {
+                println!("info string malformed position command");
+                return;
+            }
+        };
+
+        // the current position is only replaced once the whole command has applied
+        let mut board = match Board::try_from_fen(&fen) {
+            Ok(board) => board,
+            Err(e) => {
+                println!("info string invalid fen: {}", e);
+                return;
+            }
+        };
+        for token in args.iter().skip(moves_at + 1) {
+            match cli::uci_to_move(&mut board, &self.mg, token) {
+                Some(mv) => board.push(mv, &self.mg),
+                None => {
+                    println!("info string illegal move {}", token);
+                    return;
+                }
+            }
+        }
+        self.board = board;
+    }
+
+    /// `setoption name <id> [value <x>]`
+    fn set_option(&mut self, args: &[&str]) {
+        let value_at = args.iter().position(|&t| t == "value").unwrap_or(args.len());
+        let name = args.get(1..value_at).unwrap_or(&[]).join(" ");
+        let value = args.get(value_at + 1..).unwrap_or(&[]).join(" ");
+
+        match name.to_ascii_lowercase().as_str() {
+            "hash" => match value.parse::<usize>() {
+                Ok(mb) => self.search_mut().tt = Arc::new(TranspositionTable::new_mb(mb.max(1))),
+                Err(_) => println!("info string bad Hash value {}", value),
+            },
+            "nnuefile" => match Nnue::load(&value) {
+                Ok(nnue) => self.search_mut().nnue = Some(Arc::new(nnue)),
+                Err(e) => println!("info string failed to load NNUE {}: {}", value, e),
+            },
+            "usennue" => self.search_mut().use_nnue = value.eq_ignore_ascii_case("true"),
+            "threads" => match value.parse::<usize>() {
+                Ok(n) => self.search_mut().threads = n.clamp(1, MAX_THREADS),
+                Err(_) => println!("info string bad Threads value {}", value),
+            },
+            "contempt" => match value.parse::<i32>() {
+                Ok(cp) => self.search_mut().contempt = cp,
+                Err(_) => println!("info string bad Contempt value {}", value),
+            },
+            "rfpmargin" | "futilitymargin" | "razormargin" => match value.parse::<i32>() {
+                Ok(cp) => {
+                    let margins = &mut self.search_mut().margins;
+                    match name.to_ascii_lowercase().as_str() {
+                        "rfpmargin" => margins.rfp = cp,
+                        "futilitymargin" => margins.futility = cp,
+                        _ => margins.razor = cp,
+                    }
+                }
+                Err(_) => println!("info string bad {} value {}", name, value),
+            },
+            "uci_chess960" => self.search_mut().chess960 = value.eq_ignore_ascii_case("true"),
+            _ => println!("info string unknown option {}", name),
+        }
+    }
+
+    fn go(&mut self, go: GoParams) {
+        if let Some(depth) = go.perft {
+            let chess960 = self.search.as_ref().is_some_and(|s| s.chess960);
+            perft::print_divide(&mut self.board, depth, &self.mg, chess960);
+            return;
+        }
+        let mut search = self.search.take().expect("searcher is owned by the worker thread");
+        let mut board = std::mem::replace(&mut self.board, Board::new());
+        let mg = Arc::clone(&self.mg);
+
+        search.stop.store(false, Ordering::Relaxed);
+        let limits = go.limits;
+
+        self.worker = Some(thread::spawn(move || {
+            let (best, _) = search.search_with_limits(&mut board, &limits, &mg);
+
+            // In infinite mode bestmove may only be sent after `stop`.
+            if limits.infinite {
+                while !search.stop.load(Ordering::Relaxed) {
+                    thread::sleep(Duration::from_millis(1));
+                }
+            }
+            println!("bestmove {}", best.to_uci_mode(search.chess960));
+            (search, board)
+        }));
+    }
+
+    fn stop(&mut self) {
+        if self.worker.is_some() {
+            self.stop.store(true, Ordering::Relaxed);
+        }
+        self.wait();
+    }
+
+    /// Blocks until the running search (if any) has reported its bestmove.
+    fn wait(&mut self) {
+        if let Some(worker) = self.worker.take() {
+            let (search, board) = worker.join().expect("search thread panicked");
+            self.search = Some(search);
+            self.board = board;
+        }
+    }
+}
+
+/// Reads UCI commands from stdin until `quit` or EOF.
+pub fn run() {
+    let nnue = match Nnue::load(DEFAULT_NNUE_PATH) {
+        Ok(nnue) => Some(nnue),
+        Err(e) => {
+            println!("info string failed to load NNUE {}: {}, using classical eval", DEFAULT_NNUE_PATH, e);
+            None
+        }
+    };
+    let mut uci = Uci::new(nnue);
+
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        if !uci.handle(line.trim()) {
+            return;
+        }
+    }
+    uci.stop();
+}